@@ -1,4 +1,8 @@
 use glam::DVec2;
+use rand::Rng;
+use sfml::graphics::Color;
+
+use crate::components::*;
 
 /// Returns vf1 and vf2 respectively
 pub fn process_collision(
@@ -18,3 +22,104 @@ pub fn process_collision(
             * (s2 - s1),
     )
 }
+
+/// Point on the segment from `a` to `b` closest to `point`
+pub fn closest_point_on_segment(point: DVec2, a: DVec2, b: DVec2) -> DVec2 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+
+    if length_squared == 0.0 {
+        return a;
+    }
+
+    a + ab * ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0)
+}
+
+/// Even-odd test against the closed polygon through `vertices`
+pub fn point_in_polygon(point: DVec2, vertices: &[DVec2]) -> bool {
+    let mut inside = false;
+
+    for (i, a) in vertices.iter().enumerate() {
+        let b = vertices[(i + 1) % vertices.len()];
+
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+        {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
+
+/// Perfectly inelastic collision turning two particles into one,
+/// conserving mass, momentum and area. The color is mass weighted.
+pub fn merge(
+    (Mass(m1), Position(p1), Velocity(v1), s1): (Mass, Position, Velocity, ShapeInfo),
+    (Mass(m2), Position(p2), Velocity(v2), s2): (Mass, Position, Velocity, ShapeInfo),
+) -> (Mass, Position, Velocity, ShapeInfo) {
+    let m = m1 + m2;
+    let w1 = m1 / m;
+    let w2 = m2 / m;
+
+    let channel = |a: u8, b: u8| (a as f64 * w1 + b as f64 * w2).round() as u8;
+
+    (
+        Mass(m),
+        Position(p1 * w1 + p2 * w2),
+        Velocity(v1 * w1 + v2 * w2),
+        ShapeInfo {
+            radius: (s1.radius.powi(2) + s2.radius.powi(2)).sqrt(),
+            color: Color::rgba(
+                channel(s1.color.r, s2.color.r),
+                channel(s1.color.g, s2.color.g),
+                channel(s1.color.b, s2.color.b),
+                channel(s1.color.a, s2.color.a),
+            ),
+        },
+    )
+}
+
+/// Positions and velocities of `count` fragments evenly spaced on a circle of radius
+/// `distance` around `pos`, flying outwards with `spread_speed`. The outward
+/// velocities cancel out, so the momentum of the original particle is conserved.
+pub fn fragment(
+    pos: DVec2,
+    vel: DVec2,
+    distance: f64,
+    count: usize,
+    spread_speed: f64,
+) -> Vec<(DVec2, DVec2)> {
+    let offset = rand::thread_rng().gen_range(0.0..std::f64::consts::TAU);
+
+    (0..count)
+        .map(|i| {
+            let direction =
+                DVec2::from_angle(offset + std::f64::consts::TAU * i as f64 / count as f64);
+            (pos + direction * distance, vel + direction * spread_speed)
+        })
+        .collect()
+}
+
+/// Smallest ring radius at which `count` fragments of `radius` don't overlap
+pub fn fragment_spacing(radius: f64, count: usize) -> f64 {
+    if count < 2 {
+        0.0
+    } else {
+        radius / (std::f64::consts::PI / count as f64).sin()
+    }
+}
+
+/// Heat flowing from the second body into the first during `dt`, for bodies with
+/// temperatures `t1`, `t2` and heat capacities `c1`, `c2` connected by `conductance`.
+/// The temperature difference decays exponentially, so large time steps never
+/// overshoot the common equilibrium temperature.
+pub fn heat_flow(t1: f64, c1: f64, t2: f64, c2: f64, conductance: f64, dt: f64) -> f64 {
+    let inverse_capacity = 1.0 / c1 + 1.0 / c2;
+
+    if inverse_capacity <= 0.0 || !inverse_capacity.is_finite() {
+        return 0.0;
+    }
+
+    (t2 - t1) * (1.0 - (-conductance * inverse_capacity * dt).exp()) / inverse_capacity
+}
@@ -1,4 +1,8 @@
 use glam::DVec2;
+use legion::Entity;
+use rand::Rng;
+
+use crate::forces::PairPotential;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Mass(pub f64);
@@ -15,10 +19,47 @@ pub struct Id(pub usize);
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Disabled;
 
+/// Marks particles picked with the selection tool
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Selected;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelectionShape {
+    Box,
+    Lasso,
+}
+
+/// Kinematic collider following the mouse
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct MouseTracker {
     pub pos: DVec2,
     pub radius: f64,
+    /// Derived from the mouse motion every frame
+    pub velocity: DVec2,
+    pub shape: MouseShape,
+}
+
+impl MouseTracker {
+    /// End points of the collider's core, it covers everything within `radius` of them
+    pub fn segment(&self) -> (DVec2, DVec2) {
+        match self.shape {
+            MouseShape::Circle => (self.pos, self.pos),
+            MouseShape::Paddle { length, angle } => {
+                let half = DVec2::from_angle(angle) * length / 2.0;
+                (self.pos - half, self.pos + half)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MouseShape {
+    Circle,
+    /// Capsule around a segment of the given length, angle in radians
+    Paddle {
+        length: f64,
+        angle: f64,
+    },
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -29,3 +70,801 @@ pub struct ShapeInfo {
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct CollisionDetectionTime(pub u128);
+
+/// What happens when two hard spheres touch
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionResponse {
+    Elastic,
+    /// The particles become one, e.g. for planetary accretion
+    Merge,
+}
+
+/// Elastic collisions faster than the threshold shatter the particles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FragmentationSettings {
+    pub enabled: bool,
+    /// Relative speed along the collision normal
+    pub threshold: f64,
+    /// Number of fragments per shattered particle
+    pub fragments: usize,
+    /// Fragments flying apart with this fraction of the impact speed
+    pub spread: f64,
+    /// Particles whose fragments would be smaller than this don't shatter
+    pub min_radius: f64,
+    /// No fragmentation beyond this many particles
+    pub max_particles: usize,
+}
+
+impl Default for FragmentationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 300.0,
+            fragments: 4,
+            spread: 0.2,
+            min_radius: 1.0,
+            max_particles: 10_000,
+        }
+    }
+}
+
+/// Per particle override of the global [`DragSettings`] coefficients
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Drag {
+    pub linear: f64,
+    pub quadratic: f64,
+}
+
+/// Global air drag, applied to every particle without a [`Drag`] component
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DragSettings {
+    pub enabled: bool,
+    pub linear: f64,
+    pub quadratic: f64,
+    /// Scale the coefficients by the particle's cross-section (its diameter in 2D)
+    pub use_cross_section: bool,
+}
+
+impl Default for DragSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            linear: 0.1,
+            quadratic: 0.001,
+            use_cross_section: false,
+        }
+    }
+}
+
+/// Mutual gravitational attraction between particles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NBodySettings {
+    pub enabled: bool,
+    /// Gravitational constant
+    pub g: f64,
+    /// Barnes-Hut opening angle, 0 means no approximation
+    pub theta: f64,
+    pub softening: f64,
+    /// Use the exact O(n²) summation instead of Barnes-Hut
+    pub exact: bool,
+    /// Compare Barnes-Hut against the exact summation every frame (slow)
+    pub measure_error: bool,
+}
+
+impl Default for NBodySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            g: 1000.0,
+            theta: 0.5,
+            softening: 5.0,
+            exact: false,
+            measure_error: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct NBodyStats {
+    /// nanoseconds
+    pub time: u128,
+    /// Mean relative error of Barnes-Hut compared to the exact summation
+    pub mean_relative_error: Option<f64>,
+}
+
+/// Electric charge, particles interact through Coulomb forces
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Charge(pub f64);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ElectrostaticsSettings {
+    pub enabled: bool,
+    /// Coulomb constant
+    pub k: f64,
+    pub softening: f64,
+    /// Charges further away than this don't interact
+    pub cutoff: f64,
+}
+
+impl Default for ElectrostaticsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            k: 10_000.0,
+            softening: 2.0,
+            cutoff: 100.0,
+        }
+    }
+}
+
+/// How charges are assigned to newly spawned particles
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChargeDistribution {
+    Neutral,
+    /// Every particle gets `+magnitude`
+    Fixed,
+    /// Positive and negative charges take turns
+    Alternating,
+    /// `±magnitude` with a random sign
+    RandomSign,
+    /// Uniformly distributed between `-magnitude` and `+magnitude`
+    Uniform,
+}
+
+impl ChargeDistribution {
+    /// `index` is the number of particles spawned so far
+    pub fn sample(self, magnitude: f64, index: u32) -> f64 {
+        let mut rng = rand::thread_rng();
+
+        match self {
+            Self::Neutral => 0.0,
+            Self::Fixed => magnitude,
+            Self::Alternating if index.is_multiple_of(2) => magnitude,
+            Self::Alternating => -magnitude,
+            Self::RandomSign if rng.gen_bool(0.5) => magnitude,
+            Self::RandomSign => -magnitude,
+            Self::Uniform => rng.gen_range(-magnitude..=magnitude),
+        }
+    }
+}
+
+/// How overlapping particles interact with each other
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InteractionMode {
+    /// `handle_collisions`
+    HardSpheres,
+    /// `apply_pair_potential`
+    PairPotential,
+    /// `apply_sph`
+    Sph,
+    /// `solve_position_based_fluid`
+    PositionBasedFluid,
+}
+
+#[derive(Clone, Debug)]
+pub struct PairPotentialSettings {
+    pub potential: PairPotential,
+    pub cutoff: f64,
+    /// Shift the energy so that it's zero at the cutoff
+    pub shift: bool,
+}
+
+impl Default for PairPotentialSettings {
+    fn default() -> Self {
+        Self {
+            potential: PairPotential::LENNARD_JONES,
+            cutoff: 25.0,
+            shift: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EnergyStats {
+    pub kinetic: f64,
+    pub potential: f64,
+}
+
+/// Damped spring between two particles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spring {
+    pub a: Entity,
+    pub b: Entity,
+    pub rest_length: f64,
+    pub stiffness: f64,
+    pub damping: f64,
+}
+
+/// Rigid link keeping two particles at a fixed distance
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DistanceConstraint {
+    pub a: Entity,
+    pub b: Entity,
+    pub length: f64,
+}
+
+/// Holds a particle in place at the given position
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pinned(pub DVec2);
+
+/// Particle held by the grab tool, pulled towards the mouse by a spring
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Grabbed {
+    pub anchor: DVec2,
+    pub anchor_velocity: DVec2,
+}
+
+/// Left click drags particles around, they are thrown with the mouse velocity on release
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GrabSettings {
+    pub enabled: bool,
+    /// Per unit mass, so heavy particles follow as readily as light ones
+    pub stiffness: f64,
+    pub damping: f64,
+}
+
+impl Default for GrabSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            stiffness: 400.0,
+            damping: 40.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConstraintSettings {
+    /// Solver iterations per frame, more means stiffer distance constraints
+    pub iterations: usize,
+}
+
+impl Default for ConstraintSettings {
+    fn default() -> Self {
+        Self { iterations: 8 }
+    }
+}
+
+/// Particles forming one deformable body, they are also linked by springs
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoftBody {
+    pub members: Vec<Entity>,
+    /// Member offsets from the centre of mass in the rest shape
+    pub rest_shape: Vec<DVec2>,
+    /// Members along the boundary in order, the polygon the pressure acts on
+    pub outline: Vec<Entity>,
+    pub rest_area: f64,
+    /// Gas pressure at the rest area, keeps rings inflated
+    pub pressure: f64,
+    /// 0..=1, how strongly the members are pulled towards the best fitting rest shape
+    pub shape_stiffness: f64,
+}
+
+/// Particles glued together, moving with a shared linear and angular velocity
+#[derive(Clone, Debug, PartialEq)]
+pub struct RigidBody {
+    /// Members and their offsets from the centre of mass at zero rotation
+    pub members: Vec<(Entity, DVec2)>,
+    /// Centre of mass
+    pub position: DVec2,
+    pub angle: f64,
+    pub velocity: DVec2,
+    pub angular_velocity: f64,
+    pub mass: f64,
+    pub inertia: f64,
+}
+
+/// The [`RigidBody`] entity a particle belongs to, members of one body don't collide
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RigidMember(pub Entity);
+
+/// Smoothed-particle hydrodynamics state, only updated in the SPH mode
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FluidState {
+    pub density: f64,
+    pub pressure: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SphSettings {
+    pub smoothing_radius: f64,
+    pub rest_density: f64,
+    /// Gas constant turning density differences into pressure
+    pub stiffness: f64,
+    pub viscosity: f64,
+    pub surface_tension: f64,
+}
+
+impl Default for SphSettings {
+    fn default() -> Self {
+        Self {
+            smoothing_radius: 20.0,
+            rest_density: 0.012,
+            stiffness: 20_000.0,
+            viscosity: 2.0,
+            surface_tension: 500.0,
+        }
+    }
+}
+
+/// Position based fluids (Macklin & Müller 2013)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PbfSettings {
+    pub smoothing_radius: f64,
+    pub rest_density: f64,
+    /// Density constraint iterations per frame
+    pub iterations: usize,
+    /// Constraint force mixing, keeps the solver stable for sparse neighbourhoods
+    pub relaxation: f64,
+    /// Artificial pressure against particle clustering
+    pub tensile_correction: f64,
+    /// XSPH viscosity
+    pub viscosity: f64,
+    pub vorticity_confinement: f64,
+}
+
+impl Default for PbfSettings {
+    fn default() -> Self {
+        Self {
+            smoothing_radius: 20.0,
+            rest_density: 0.012,
+            iterations: 4,
+            relaxation: 1e-6,
+            tensile_correction: 0.001,
+            viscosity: 0.1,
+            vorticity_confinement: 0.0,
+        }
+    }
+}
+
+/// In kelvin
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Temperature(pub f64);
+
+/// Specific heat, the heat capacity of a particle is this times its mass
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeatCapacity(pub f64);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wall {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// Heat conduction between touching particles and between particles and the walls
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HeatSettings {
+    pub enabled: bool,
+    /// Heat flow per unit of contact width and temperature difference
+    pub conductance: f64,
+    /// Walls acting as heat baths at a fixed temperature, indexed by [`Wall`],
+    /// `None` walls are insulating
+    pub baths: [Option<f64>; 4],
+    pub bath_conductance: f64,
+}
+
+impl Default for HeatSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            conductance: 1.0,
+            baths: [None; 4],
+            bath_conductance: 1.0,
+        }
+    }
+}
+
+/// Index into [`Chemistry::species`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Species(pub usize);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpeciesDefinition {
+    pub name: String,
+    pub radius: f64,
+    pub mass: f64,
+    pub color: sfml::graphics::Color,
+}
+
+/// `A + B -> products` on collision. Two products take the places of the reactants,
+/// any other number is spawned around their centre of mass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReactionRule {
+    pub reactants: [usize; 2],
+    pub products: Vec<usize>,
+    /// Chance of reacting once the activation energy is reached
+    pub probability: f64,
+    /// Minimum kinetic energy of the approach along the line of centres
+    pub activation_energy: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chemistry {
+    pub enabled: bool,
+    pub species: Vec<SpeciesDefinition>,
+    pub reactions: Vec<ReactionRule>,
+}
+
+impl Chemistry {
+    /// The first rule for the pair of species, and whether the reactants are
+    /// listed in the opposite order
+    pub fn reaction(&self, a: usize, b: usize) -> Option<(&ReactionRule, bool)> {
+        self.reactions.iter().find_map(|rule| {
+            if rule.reactants == [a, b] {
+                Some((rule, false))
+            } else if rule.reactants == [b, a] {
+                Some((rule, true))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+impl Default for Chemistry {
+    fn default() -> Self {
+        use sfml::graphics::Color;
+
+        let species = |name: &str, radius, mass, color| SpeciesDefinition {
+            name: name.to_string(),
+            radius,
+            mass,
+            color,
+        };
+
+        Self {
+            enabled: false,
+            species: vec![
+                species("A", 5.0, 1.0, Color::rgb(230, 80, 80)),
+                species("B", 5.0, 1.0, Color::rgb(80, 130, 230)),
+                species("C", 7.0, 2.0, Color::rgb(90, 210, 110)),
+            ],
+            reactions: vec![ReactionRule {
+                reactants: [0, 1],
+                products: vec![2],
+                probability: 1.0,
+                activation_energy: 0.0,
+            }],
+        }
+    }
+}
+
+/// Species counts sampled at a fixed interval, oldest first
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpeciesHistory {
+    pub samples: std::collections::VecDeque<Vec<usize>>,
+    pub capacity: usize,
+    /// Seconds between samples
+    pub interval: f64,
+    pub elapsed: f64,
+}
+
+impl Default for SpeciesHistory {
+    fn default() -> Self {
+        Self {
+            samples: Default::default(),
+            capacity: 600,
+            interval: 0.1,
+            elapsed: 0.0,
+        }
+    }
+}
+
+/// Particles only collide when each one's layer is in the other's mask.
+/// Particles without the component behave like [`CollisionGroup::default`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionGroup {
+    pub layer: u32,
+    pub mask: u32,
+}
+
+impl CollisionGroup {
+    /// Layer of the window borders
+    pub const WALLS: u32 = 1 << 31;
+    /// Layer of the mouse collider
+    pub const MOUSE: u32 = 1 << 30;
+
+    pub fn collides_with(&self, other: &Self) -> bool {
+        self.layer & other.mask != 0 && other.layer & self.mask != 0
+    }
+
+    pub fn hits(&self, layer: u32) -> bool {
+        self.mask & layer != 0
+    }
+}
+
+impl Default for CollisionGroup {
+    fn default() -> Self {
+        Self {
+            layer: 1,
+            mask: u32::MAX,
+        }
+    }
+}
+
+/// Contact between two particles resolved by `handle_collisions`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CollisionEvent {
+    pub a: Entity,
+    pub b: Entity,
+    pub point: DVec2,
+    /// Unit vector pointing from `b` to `a`
+    pub normal: DVec2,
+    /// Magnitude of the momentum exchanged
+    pub impulse: f64,
+}
+
+/// Particle bouncing off one of the window borders
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WallHit {
+    pub entity: Entity,
+    pub wall: Wall,
+    pub point: DVec2,
+    /// Unit vector pointing away from the wall
+    pub normal: DVec2,
+    pub impulse: f64,
+}
+
+/// Contacts of the current step, cleared at the start of every step
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CollisionEvents {
+    pub particles: Vec<CollisionEvent>,
+    pub walls: Vec<WallHit>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Region {
+    Rectangle { min: DVec2, max: DVec2 },
+    Circle { centre: DVec2, radius: f64 },
+}
+
+impl Region {
+    pub fn contains(&self, point: DVec2) -> bool {
+        match *self {
+            Region::Rectangle { min, max } => point.cmpge(min).all() && point.cmple(max).all(),
+            Region::Circle { centre, radius } => point.distance_squared(centre) <= radius * radius,
+        }
+    }
+
+    /// Smallest circle around the region, for spatial queries
+    pub fn bounding_circle(&self) -> (DVec2, f64) {
+        match *self {
+            Region::Rectangle { min, max } => ((min + max) / 2.0, (max - min).length() / 2.0),
+            Region::Circle { centre, radius } => (centre, radius),
+        }
+    }
+}
+
+/// Trigger region which doesn't collide, but tracks the particles whose centres are inside
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sensor {
+    pub region: Region,
+    /// Ids of the particles inside during the last step, sorted
+    pub inside: Vec<usize>,
+    pub entered: Vec<usize>,
+    pub exited: Vec<usize>,
+    /// Running totals since the sensor was created
+    pub total_entered: usize,
+    pub total_exited: usize,
+}
+
+impl Sensor {
+    pub fn new(region: Region) -> Self {
+        Self {
+            region,
+            inside: vec![],
+            entered: vec![],
+            exited: vec![],
+            total_entered: 0,
+            total_exited: 0,
+        }
+    }
+}
+
+/// Uniformly sampled range, `min == max` always gives `min`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interval {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Interval {
+    pub fn constant(value: f64) -> Self {
+        Self {
+            min: value,
+            max: value,
+        }
+    }
+
+    pub fn sample(&self, rng: &mut impl Rng) -> f64 {
+        if self.min < self.max {
+            rng.gen_range(self.min..=self.max)
+        } else {
+            self.min
+        }
+    }
+}
+
+/// Spawns particles at a fixed rate into a cone
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Emitter {
+    pub position: DVec2,
+    /// Angle of the cone's axis in radians, the y axis points down
+    pub direction: f64,
+    /// Opening angle of the cone in radians
+    pub spread: f64,
+    pub speed: Interval,
+    /// Particles per second
+    pub rate: f64,
+    pub radius: Interval,
+    pub mass: Interval,
+    pub color: sfml::graphics::Color,
+    pub temperature: f64,
+    /// The emitter stops after emitting this many particles
+    pub max_count: Option<usize>,
+    /// Seconds the emitted particles live for
+    pub lifetime: Option<f64>,
+    pub emitted: usize,
+    /// Fraction of a particle carried over to the next step
+    pub accumulator: f64,
+}
+
+impl Default for Emitter {
+    fn default() -> Self {
+        Self {
+            position: DVec2::ZERO,
+            direction: std::f64::consts::FRAC_PI_2,
+            spread: 0.5,
+            speed: Interval {
+                min: 50.0,
+                max: 100.0,
+            },
+            rate: 20.0,
+            radius: Interval::constant(4.0),
+            mass: Interval::constant(1.0),
+            color: sfml::graphics::Color::rgb(120, 200, 255),
+            temperature: 300.0,
+            max_count: None,
+            lifetime: None,
+            emitted: 0,
+            accumulator: 0.0,
+        }
+    }
+}
+
+/// Seconds left until the particle is despawned
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lifetime(pub f64);
+
+/// Despawns every particle whose centre enters the region
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sink {
+    pub region: Region,
+    pub removed: usize,
+    pub removed_mass: f64,
+}
+
+impl Sink {
+    pub fn new(region: Region) -> Self {
+        Self {
+            region,
+            removed: 0,
+            removed_mass: 0.0,
+        }
+    }
+}
+
+/// How a field weakens towards its edge
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Falloff {
+    None,
+    Linear,
+    Quadratic,
+}
+
+impl Falloff {
+    /// `t` is the distance relative to the field's radius
+    pub fn factor(self, t: f64) -> f64 {
+        match self {
+            Falloff::None => 1.0,
+            Falloff::Linear => 1.0 - t,
+            Falloff::Quadratic => (1.0 - t).powi(2),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ForceField {
+    /// Uniform acceleration inside the region
+    Wind { region: Region, acceleration: DVec2 },
+    /// Swirls particles around the centre
+    Vortex {
+        centre: DVec2,
+        radius: f64,
+        strength: f64,
+        falloff: Falloff,
+    },
+    /// Pulls particles towards the centre, negative strengths push them away
+    Radial {
+        centre: DVec2,
+        radius: f64,
+        strength: f64,
+        falloff: Falloff,
+    },
+}
+
+impl ForceField {
+    pub fn acceleration(&self, pos: DVec2) -> DVec2 {
+        match *self {
+            ForceField::Wind {
+                region,
+                acceleration,
+            } => {
+                if region.contains(pos) {
+                    acceleration
+                } else {
+                    DVec2::ZERO
+                }
+            }
+            ForceField::Vortex {
+                centre,
+                radius,
+                strength,
+                falloff,
+            }
+            | ForceField::Radial {
+                centre,
+                radius,
+                strength,
+                falloff,
+            } => {
+                let offset = pos - centre;
+                let distance = offset.length();
+
+                if distance == 0.0 || distance > radius {
+                    return DVec2::ZERO;
+                }
+
+                let direction = match self {
+                    ForceField::Vortex { .. } => offset.perp() / distance,
+                    _ => -offset / distance,
+                };
+
+                direction * strength * falloff.factor(distance / radius)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseMode {
+    /// Particles bounce off the [`MouseTracker`]
+    Push,
+    /// The mouse acts as a radial force field
+    Attract,
+}
+
+/// How the mouse acts on particles while space is held
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MouseSettings {
+    pub mode: MouseMode,
+    /// Of bounces off the pusher, 1 is elastic
+    pub restitution: f64,
+    pub strength: f64,
+    pub radius: f64,
+    pub falloff: Falloff,
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        Self {
+            mode: MouseMode::Push,
+            restitution: 1.0,
+            strength: 1000.0,
+            radius: 200.0,
+            falloff: Falloff::Linear,
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use super::*;
 
+use std::f64::consts::{FRAC_PI_2, PI};
+use std::collections::VecDeque;
 use std::time::Instant;
 
 use egui_sfml::SfEgui;
@@ -15,6 +17,10 @@ use glam::DVec2;
 use super::systems as sys;
 
 use components::*;
+use forces::PairPotential;
+use neighbours::NeighbourList;
+use renderer::ColorMode;
+use spawn::{LinkKind, ParticleTemplate};
 
 pub fn run() {
     let texture_image = renderer::circle(100, Color::WHITE);
@@ -37,24 +43,74 @@ pub fn run() {
 
     let mut resources = Resources::default();
     let mut schedule = Schedule::builder()
+        .add_system(sys::clear_collision_events_system())
+        .add_system(sys::update_sensors_system())
+        .add_system(sys::drain_sinks_system())
+        .add_system(sys::update_neighbour_list_system())
+        .add_system(sys::apply_reactions_system())
         .add_system(sys::handle_collisions_system())
         .add_system(sys::handle_mouse_collision_system())
+        .add_system(sys::apply_gravitational_attraction_system())
+        .add_system(sys::apply_coulomb_forces_system())
+        .add_system(sys::apply_pair_potential_system())
+        .add_system(sys::apply_sph_system())
+        .add_system(sys::apply_springs_system())
+        .add_system(sys::apply_soft_bodies_system())
+        .add_system(sys::apply_force_fields_system())
+        .add_system(sys::run_emitters_system())
+        .add_system(sys::expire_particles_system())
         .flush()
+        .add_system(sys::drag_grabbed_system())
+        .add_system(sys::apply_drag_system())
+        .add_system(sys::apply_flow_drag_system())
         .add_system(sys::update_positions_system())
+        .add_system(sys::solve_position_based_fluid_system())
+        .add_system(sys::solve_distance_constraints_system())
         .add_system(sys::check_wall_collision_system())
+        .add_system(sys::solve_rigid_bodies_system())
+        .add_system(sys::enforce_pins_system())
+        .add_system(sys::measure_energy_system())
+        .add_system(sys::record_species_history_system())
         .build();
 
-    // effective radius: radius + outline thickness
     let mt = MouseTracker {
         radius: 54.0,
         pos: DVec2::new(-100., -100.),
+        velocity: DVec2::ZERO,
+        shape: MouseShape::Circle,
     };
 
     let tracker_entity = world.push((mt, Disabled));
 
     resources.insert(window.size());
-    resources.insert(mt);
     resources.insert(CollisionDetectionTime(0));
+    resources.insert(DragSettings::default());
+    resources.insert(NBodySettings::default());
+    resources.insert(NBodyStats::default());
+    resources.insert(ElectrostaticsSettings::default());
+    resources.insert(InteractionMode::HardSpheres);
+    resources.insert(CollisionResponse::Elastic);
+    resources.insert(FragmentationSettings::default());
+    resources.insert(PairPotentialSettings::default());
+    resources.insert(SphSettings::default());
+    resources.insert(PbfSettings::default());
+    resources.insert(EnergyStats::default());
+    resources.insert(NeighbourList::default());
+    resources.insert(ConstraintSettings::default());
+    resources.insert(HeatSettings::default());
+    resources.insert(Chemistry::default());
+    resources.insert(SpeciesHistory::default());
+    resources.insert(CollisionEvents::default());
+    resources.insert(MouseSettings::default());
+    resources.insert(GrabSettings::default());
+    resources.insert(flow::VectorField::default());
+    // empty until the first frame that needs a quadtree builds one
+    resources.insert(quadtree::QuadTree::<usize>::new(8, Rect {
+        left: 0.,
+        top: 0.,
+        width: window.size().x as _,
+        height: window.size().y as _,
+    }));
 
     let mut mouse_tracker = CircleShape::new(0.0, 1000);
     mouse_tracker.set_origin((50.0, 50.0));
@@ -67,6 +123,10 @@ pub fn run() {
 
     let mut pressed = false;
     let mut num_particles = 0;
+    // the last few mouse positions and the seconds since the one before, so the
+    // velocity a throw is released with doesn't drop to zero after one still frame
+    let mut mouse_history: VecDeque<(DVec2, f64)> = VecDeque::new();
+    let mut mouse_velocity = DVec2::ZERO;
 
     // used in egui
     let mut slower_collision_detection = false;
@@ -76,34 +136,72 @@ pub fn run() {
     let mut fps_limit = 120;
     let mut particle_radius = 5;
     let mut show_info = false;
+    let mut charge_distribution = ChargeDistribution::Neutral;
+    let mut charge_magnitude = 1.0;
+    let mut color_mode = ColorMode::Random;
+    let mut rigid_links = false;
+    let mut spring_stiffness = 500.0;
+    let mut spring_damping = 5.0;
+    let mut structure_size = 20;
+    let mut soft_pressure = 100.0;
+    let mut soft_shape_stiffness = 0.05;
+    let mut spawn_temperature = 300.0;
+    let mut specific_heat = 1.0;
+    let mut max_temperature = 1000.0;
+    let mut spawn_species = None;
+    let mut spawn_group = CollisionGroup::default();
+    let mut scene_path = String::from("scene.txt");
+    let mut scene_status = String::new();
+    let mut flow_path = String::from("flow.csv");
+    let mut flow_status = String::new();
+    let mut potential_path = String::from("potential.csv");
+    let mut potential_status = String::new();
+    let mut draw_flow = false;
+    let mut selection_shape = SelectionShape::Box;
+    // corners of the box or the lasso outline while dragging
+    let mut selection_drag: Option<Vec<DVec2>> = None;
+    let mut bulk_velocity = DVec2::ZERO;
+    let mut bulk_mass = 1.0;
+    let mut bulk_radius = 5.0;
+    let mut bulk_color = [255, 255, 255];
+    let mut bulk_drag = Drag {
+        linear: DragSettings::default().linear,
+        quadratic: DragSettings::default().quadratic,
+    };
+    // left click waiting to be resolved to a particle
+    let mut click: Option<DVec2> = None;
+    let mut inspected: Option<Entity> = None;
+    let mut speed_history = VecDeque::new();
+    let mut inspect_range = 30.0;
     //
 
     // let mut shape = CircleShape::new(5.0, 30);
     let mut shape = Sprite::new();
     shape.set_texture(&texture, true);
 
-    let add_ball = |x, y, world: &mut World, num_particles: &mut u32, particle_radius: f64| {
+    let add_ball = |x,
+                    y,
+                    world: &mut World,
+                    num_particles: &mut u32,
+                    template: ParticleTemplate,
+                    species: Option<usize>,
+                    group: CollisionGroup| {
         *num_particles += 1;
-        let _ = world.push((
-            Id(id()),
-            // Mass(thread_rng().gen_range(50.0..=100.0)),
-            Mass(1.0),
-            Position(DVec2 { x, y }),
-            Velocity(DVec2 {
-                x: thread_rng().gen_range(-30.0..=30.0),
-                y: thread_rng().gen_range(-30.0..=30.0),
-            }),
-            ShapeInfo {
-                radius: particle_radius,
-                // radius: 100.0,
-                // color: Color::GREEN,
-                color: Color::rgb(
-                    thread_rng().gen_range(0..=255),
-                    thread_rng().gen_range(0..=255),
-                    thread_rng().gen_range(0..=255),
-                ),
-            },
-        ));
+
+        let entity = world.push(template.components(DVec2 { x, y }, DVec2 {
+            x: thread_rng().gen_range(-30.0..=30.0),
+            y: thread_rng().gen_range(-30.0..=30.0),
+        }));
+
+        let mut entry = world.entry(entity).unwrap();
+
+        if let Some(species) = species {
+            entry.add_component(Species(species));
+        }
+
+        if group != CollisionGroup::default() {
+            entry.add_component(group);
+        }
     };
 
     while window.is_open() {
@@ -114,6 +212,24 @@ pub fn run() {
         }
 
         let dt = clock.restart();
+
+        let spawned_template = |chemistry: &Chemistry, num_particles| match spawn_species
+            .and_then(|species| chemistry.species.get(species))
+        {
+            Some(species) => {
+                ParticleTemplate::of_species(species, spawn_temperature, specific_heat)
+            }
+            None => ParticleTemplate {
+                radius: particle_radius as f64,
+                // mass: thread_rng().gen_range(50.0..=100.0),
+                mass: 1.0,
+                charge: charge_distribution.sample(charge_magnitude, num_particles),
+                color: random_color(),
+                temperature: spawn_temperature,
+                specific_heat,
+            },
+        };
+
         while let Some(event) = window.poll_event() {
             sfegui.add_event(&event);
             match event {
@@ -127,12 +243,72 @@ pub fn run() {
 
                 Event::KeyReleased {
                     code: Key::Space, ..
+                } => world.entry(tracker_entity).unwrap().add_component(Disabled),
+
+                Event::MouseButtonPressed {
+                    button: mouse::Button::Left,
+                    x,
+                    y,
+                } if Key::LShift.is_pressed() && !sfegui.context().wants_pointer_input() => {
+                    selection_drag = Some(vec![DVec2::new(x as _, y as _)]);
+                }
+
+                // resolved once the quadtree is up to date
+                Event::MouseButtonPressed {
+                    button: mouse::Button::Left,
+                    x,
+                    y,
+                } if !sfegui.context().wants_pointer_input() => {
+                    click = Some(DVec2::new(x as _, y as _));
+                }
+
+                Event::MouseButtonReleased {
+                    button: mouse::Button::Left,
+                    ..
                 } => {
-                    world.entry(tracker_entity).unwrap().add_component(Disabled);
-                    <&mut MouseTracker>::query().for_each_mut(&mut world, |mt| {
-                        mt.pos = DVec2::new(-100., -100.);
-                        resources.insert(*mt);
-                    });
+                    // a click without dragging clears the selection
+                    if let Some(points) = selection_drag.take() {
+                        let bounds = Region::Rectangle {
+                            min: points[0].min(*points.last().unwrap()),
+                            max: points[0].max(*points.last().unwrap()),
+                        };
+                        let inside = |pos: DVec2| match selection_shape {
+                            SelectionShape::Box => bounds.contains(pos),
+                            SelectionShape::Lasso => collision::point_in_polygon(pos, &points),
+                        };
+
+                        let particles = <(Entity, &Position)>::query()
+                            .filter(component::<Id>())
+                            .iter(&world)
+                            .map(|(entity, Position(pos))| (*entity, inside(*pos)))
+                            .collect::<Vec<_>>();
+
+                        for (entity, inside) in particles {
+                            let mut entry = world.entry(entity).unwrap();
+
+                            if inside {
+                                entry.add_component(Selected);
+                            } else {
+                                entry.remove_component::<Selected>();
+                            }
+                        }
+                    }
+
+                    let grabbed = <Entity>::query()
+                        .filter(component::<Grabbed>())
+                        .iter(&world)
+                        .copied()
+                        .collect::<Vec<_>>();
+
+                    // thrown with the mouse's velocity
+                    for entity in grabbed {
+                        let mut entry = world.entry(entity).unwrap();
+                        entry.remove_component::<Grabbed>();
+
+                        if let Ok(Velocity(vel)) = entry.get_component_mut::<Velocity>() {
+                            *vel = mouse_velocity;
+                        }
+                    }
                 }
 
                 Event::KeyPressed {
@@ -148,75 +324,146 @@ pub fn run() {
                     y,
                 } => {
                     pressed = true;
+                    let template =
+                        spawned_template(&resources.get::<Chemistry>().unwrap(), num_particles);
                     add_ball(
                         x as _,
                         y as _,
                         &mut world,
                         &mut num_particles,
-                        particle_radius as f64,
+                        template,
+                        spawn_species,
+                        spawn_group,
                     );
                 }
 
                 Event::MouseMoved { x, y } if pressed => {
+                    let template =
+                        spawned_template(&resources.get::<Chemistry>().unwrap(), num_particles);
                     add_ball(
                         x as _,
                         y as _,
                         &mut world,
                         &mut num_particles,
-                        particle_radius as f64,
+                        template,
+                        spawn_species,
+                        spawn_group,
                     );
-                    <&mut MouseTracker>::query()
-                        .filter(!component::<Disabled>())
-                        .for_each_mut(&mut world, |m| {
-                            m.pos = DVec2 {
-                                x: x as _,
-                                y: y as _,
-                            };
-
-                            resources.insert(*m);
-                        });
                 }
 
-                Event::MouseMoved { x, y } => <&mut MouseTracker>::query()
-                    .filter(!component::<Disabled>())
-                    .for_each_mut(&mut world, |m| {
-                        m.pos = DVec2 {
-                            x: x as _,
-                            y: y as _,
-                        };
+                Event::MouseMoved { x, y } => {
+                    if let Some(points) = &mut selection_drag {
+                        if selection_shape == SelectionShape::Box {
+                            points.truncate(1);
+                        }
 
-                        resources.insert(*m);
-                    }),
+                        points.push(DVec2::new(x as _, y as _));
+                    }
+                }
 
                 _ => {}
             }
         }
 
+        window.clear(Color::BLACK);
+
+        // with cached neighbour lists the quadtree is only needed for drawing, gravity and sensors
+        let needs_quadtree = {
+            let nbody = resources.get::<NBodySettings>().unwrap();
+
+            !resources.get::<NeighbourList>().unwrap().enabled
+                || draw_quadtree
+                || (nbody.enabled && !nbody.exact)
+                || <&Sensor>::query().iter(&world).next().is_some()
+                || click.is_some()
+                || inspected.is_some()
+        };
+
         let timer = Instant::now();
-        let mut query = <(&Id, &Position, &ShapeInfo)>::query();
-        let mut qt = quadtree::QuadTree::<usize>::new(quad_capacity, Rect {
-            left: 0.,
-            top: 0.,
-            width: window.size().x as _,
-            height: window.size().y as _,
-        });
 
-        query.for_each(
-            &world,
-            |(Id(id), Position(position), ShapeInfo { radius, .. })| {
-                qt.push((*position, *radius, *id));
-            },
-        );
+        if needs_quadtree {
+            let mut query = <(&Id, &Position, &ShapeInfo)>::query();
+            let mut qt = quadtree::QuadTree::<usize>::new(quad_capacity, Rect {
+                left: 0.,
+                top: 0.,
+                width: window.size().x as _,
+                height: window.size().y as _,
+            });
+
+            query.for_each(
+                &world,
+                |(Id(id), Position(position), ShapeInfo { radius, .. })| {
+                    qt.push((*position, *radius, *id));
+                },
+            );
+
+            if draw_quadtree {
+                qt.draw(&mut window, 0);
+            }
+
+            resources.insert(qt);
+        }
 
         let qt_build_time = timer.elapsed().as_nanos() as f64 / 1e6;
 
-        window.clear(Color::BLACK);
+        if let Some(point) = click.take() {
+            let candidates = resources
+                .get::<quadtree::QuadTree<usize>>()
+                .unwrap()
+                .query(point, 0.0);
+
+            // the particle under the cursor whose centre is closest
+            let picked = <(Entity, &Id, &Position, &ShapeInfo)>::query()
+                .iter(&world)
+                .filter(|(_, Id(id), Position(pos), ShapeInfo { radius, .. })| {
+                    candidates.contains(id) && pos.distance(point) <= *radius
+                })
+                .min_by(|(_, _, Position(a), _), (_, _, Position(b), _)| {
+                    a.distance(point).total_cmp(&b.distance(point))
+                })
+                .map(|(entity, ..)| *entity);
 
-        if draw_quadtree {
-            qt.draw(&mut window, 0);
+            if let Some(entity) = picked {
+                if inspected != Some(entity) {
+                    inspected = Some(entity);
+                    speed_history.clear();
+                }
+
+                if resources.get::<GrabSettings>().unwrap().enabled {
+                    world.entry(entity).unwrap().add_component(Grabbed {
+                        anchor: point,
+                        anchor_velocity: mouse_velocity,
+                    });
+                }
+            }
+        }
+
+        let mouse = window.mouse_position();
+        let mouse_pos = DVec2::new(mouse.x as _, mouse.y as _);
+
+        mouse_history.push_back((mouse_pos, dt.as_seconds() as f64));
+        if mouse_history.len() > 5 {
+            mouse_history.pop_front();
+        }
+
+        let elapsed = mouse_history.iter().skip(1).map(|(_, dt)| dt).sum::<f64>();
+        if elapsed > 0.0 {
+            mouse_velocity = (mouse_pos - mouse_history[0].0) / elapsed;
         }
 
-        resources.insert(qt);
+        <&mut Grabbed>::query().for_each_mut(&mut world, |grabbed| {
+            grabbed.anchor = mouse_pos;
+            grabbed.anchor_velocity = mouse_velocity;
+        });
+
+        // the tracker is kinematic, its velocity follows the mouse
+        <&mut MouseTracker>::query()
+            .filter(!component::<Disabled>())
+            .for_each_mut(&mut world, |tracker| {
+                tracker.pos = mouse_pos;
+                tracker.velocity = mouse_velocity;
+            });
+
         resources.insert(slower_collision_detection);
         resources.insert(dt.as_seconds());
 
@@ -225,37 +472,265 @@ pub fn run() {
         let frame_time = dt.as_milliseconds();
 
         let timer = Instant::now();
-        <(&Position, &ShapeInfo)>::query().iter(&world).for_each(
-            |(Position(DVec2 { x, y }), ShapeInfo { radius, color })| {
-                let scale = *radius as f32 / 100.0;
-                shape.set_scale((scale, scale));
 
-                shape.set_position((*x as _, *y as _));
-                shape.set_color(*color);
+        let mut links = vec![];
+        let mut link_line = |a, b, color| {
+            let position = |e| {
+                world
+                    .entry_ref(e)
+                    .ok()
+                    .and_then(|e| e.get_component::<Position>().ok().map(|p| p.0))
+            };
+
+            if let (Some(a), Some(b)) = (position(a), position(b)) {
+                links.push(Vertex::with_pos_color((a.x as _, a.y as _).into(), color));
+                links.push(Vertex::with_pos_color((b.x as _, b.y as _).into(), color));
+            }
+        };
 
-                shape.set_color(*color);
-                // shape.set_radius(*radius as _);
-                shape.set_origin((*radius as _, *radius as _));
+        <&Spring>::query()
+            .iter(&world)
+            .for_each(|s| link_line(s.a, s.b, Color::rgb(150, 150, 150)));
+        <&DistanceConstraint>::query()
+            .iter(&world)
+            .for_each(|c| link_line(c.a, c.b, Color::rgb(120, 170, 255)));
 
-                window.draw(&shape);
-            },
-        );
+        for (Position(pos), grabbed) in <(&Position, &Grabbed)>::query().iter(&world) {
+            let color = Color::rgb(255, 200, 120);
 
-        let draw_time = timer.elapsed().as_nanos() as f64 / 1e6;
+            links.push(Vertex::with_pos_color(
+                (pos.x as _, pos.y as _).into(),
+                color,
+            ));
+            links.push(Vertex::with_pos_color(
+                (grabbed.anchor.x as _, grabbed.anchor.y as _).into(),
+                color,
+            ));
+        }
 
-        <&MouseTracker>::query()
-            .filter(!component::<Disabled>())
+        // emitters are drawn as the outline of their cone
+        for emitter in <&Emitter>::query().iter(&world) {
+            let color = Color::rgb(120, 255, 160);
+            let start = emitter.position;
+
+            for angle in [-0.5, 0.0, 0.5].map(|side| emitter.direction + side * emitter.spread) {
+                let end = start + DVec2::from_angle(angle) * 30.0;
+
+                links.push(Vertex::with_pos_color(
+                    (start.x as _, start.y as _).into(),
+                    color,
+                ));
+                links.push(Vertex::with_pos_color(
+                    (end.x as _, end.y as _).into(),
+                    color,
+                ));
+            }
+        }
+
+        // flow velocities are drawn as the distance covered in a tenth of a second
+        if draw_flow {
+            let field = resources.get::<flow::VectorField>().unwrap();
+            let step = (field.columns.max(field.rows) / 50).max(1);
+
+            for row in (0..field.rows).step_by(step) {
+                for column in (0..field.columns).step_by(step) {
+                    let start =
+                        field.origin + field.spacing * DVec2::new(column as f64, row as f64);
+                    let end = start + field.velocities[row * field.columns + column] * 0.1;
+                    let color = Color::rgb(90, 90, 140);
+
+                    links.push(Vertex::with_pos_color(
+                        (start.x as _, start.y as _).into(),
+                        color,
+                    ));
+                    links.push(Vertex::with_pos_color(
+                        (end.x as _, end.y as _).into(),
+                        color,
+                    ));
+                }
+            }
+        }
+
+        window.draw_primitives(&links, PrimitiveType::LINES, &RenderStates::DEFAULT);
+
+        for sensor in <&Sensor>::query().iter(&world) {
+            renderer::draw_region(
+                &mut window,
+                &sensor.region,
+                Color::rgba(255, 220, 80, 30),
+                Color::rgb(255, 220, 80),
+            );
+        }
+
+        for field in <&ForceField>::query().iter(&world) {
+            let (region, color) = match *field {
+                ForceField::Wind { region, .. } => (region, Color::rgb(120, 200, 255)),
+                ForceField::Vortex { centre, radius, .. } => {
+                    (Region::Circle { centre, radius }, Color::rgb(190, 120, 255))
+                }
+                ForceField::Radial {
+                    centre,
+                    radius,
+                    strength,
+                    ..
+                } => (
+                    Region::Circle { centre, radius },
+                    if strength >= 0.0 {
+                        Color::rgb(80, 140, 255)
+                    } else {
+                        Color::rgb(255, 150, 60)
+                    },
+                ),
+            };
+
+            renderer::draw_region(
+                &mut window,
+                &region,
+                Color::rgba(color.r, color.g, color.b, 20),
+                color,
+            );
+        }
+
+        for sink in <&Sink>::query().iter(&world) {
+            renderer::draw_region(
+                &mut window,
+                &sink.region,
+                Color::rgba(255, 60, 60, 40),
+                Color::rgb(255, 60, 60),
+            );
+        }
+
+        <(&Position, &ShapeInfo, Option<&Charge>, Option<&Temperature>)>::query()
             .iter(&world)
             .for_each(
-                |MouseTracker {
-                     pos: DVec2 { x, y },
-                     ..
-                 }| {
-                    mouse_tracker.set_position((*x as _, *y as _));
-                    window.draw(&mouse_tracker);
+                |(Position(DVec2 { x, y }), ShapeInfo { radius, color }, charge, temperature)| {
+                    let color = match color_mode {
+                        ColorMode::Random => *color,
+                        ColorMode::ChargeSign => {
+                            renderer::charge_color(charge.map_or(0.0, |c| c.0), charge_magnitude)
+                        }
+                        ColorMode::Temperature => {
+                            renderer::heat_color(temperature.map_or(0.0, |t| t.0), max_temperature)
+                        }
+                    };
+
+                    let scale = *radius as f32 / 100.0;
+                    shape.set_scale((scale, scale));
+
+                    shape.set_position((*x as _, *y as _));
+                    shape.set_color(color);
+
+                    shape.set_color(color);
+                    // shape.set_radius(*radius as _);
+                    shape.set_origin((*radius as _, *radius as _));
+
+                    window.draw(&shape);
                 },
             );
 
+        let mut highlight = CircleShape::new(0.0, 30);
+        highlight.set_fill_color(Color::TRANSPARENT);
+        highlight.set_outline_color(Color::rgb(255, 255, 120));
+        highlight.set_outline_thickness(2.0);
+
+        for (Position(pos), ShapeInfo { radius, .. }) in <(&Position, &ShapeInfo)>::query()
+            .filter(component::<Selected>())
+            .iter(&world)
+        {
+            highlight.set_radius(*radius as f32 + 2.0);
+            highlight.set_origin((*radius as f32 + 2.0, *radius as f32 + 2.0));
+            highlight.set_position((pos.x as _, pos.y as _));
+            window.draw(&highlight);
+        }
+
+        if let Some(Ok(entry)) = inspected.map(|entity| world.entry_ref(entity))
+            && let (Ok(Position(pos)), Ok(ShapeInfo { radius, .. })) = (
+                entry.get_component::<Position>(),
+                entry.get_component::<ShapeInfo>(),
+            )
+        {
+            highlight.set_outline_color(Color::rgb(120, 255, 255));
+            highlight.set_radius(*radius as f32 + 2.0);
+            highlight.set_origin((*radius as f32 + 2.0, *radius as f32 + 2.0));
+            highlight.set_position((pos.x as _, pos.y as _));
+            window.draw(&highlight);
+        }
+
+        match (&selection_drag, selection_shape) {
+            (Some(points), SelectionShape::Box) => renderer::draw_region(
+                &mut window,
+                &Region::Rectangle {
+                    min: points[0].min(*points.last().unwrap()),
+                    max: points[0].max(*points.last().unwrap()),
+                },
+                Color::rgba(255, 255, 120, 20),
+                Color::rgb(255, 255, 120),
+            ),
+            (Some(points), SelectionShape::Lasso) => {
+                let outline = points
+                    .iter()
+                    .chain(points.first())
+                    .map(|p| {
+                        Vertex::with_pos_color(
+                            (p.x as _, p.y as _).into(),
+                            Color::rgb(255, 255, 120),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                window.draw_primitives(&outline, PrimitiveType::LINE_STRIP, &RenderStates::DEFAULT);
+            }
+            (None, _) => {}
+        }
+
+        let draw_time = timer.elapsed().as_nanos() as f64 / 1e6;
+
+        // attracting, the tracker shows the reach of the field instead
+        let mouse_settings = *resources.get::<MouseSettings>().unwrap();
+
+        <&MouseTracker>::query()
+            .filter(!component::<Disabled>())
+            .iter(&world)
+            .for_each(|tracker| {
+                // the outline is drawn inside the collider
+                let outline = mouse_tracker.outline_thickness();
+
+                match (mouse_settings.mode, tracker.shape) {
+                    (MouseMode::Push, MouseShape::Paddle { .. }) => {
+                        let (start, end) = tracker.segment();
+                        let radius = tracker.radius - outline as f64;
+                        let angle = (end - start).to_angle();
+                        let points = 32;
+                        let mut paddle = ConvexShape::new(2 * points);
+
+                        // half circles around both ends make up the capsule
+                        for i in 0..points {
+                            let t = i as f64 / (points - 1) as f64 * PI;
+                            let front = end + DVec2::from_angle(angle - FRAC_PI_2 + t) * radius;
+                            let back = start + DVec2::from_angle(angle + FRAC_PI_2 + t) * radius;
+                            paddle.set_point(i, (front.x as f32, front.y as f32));
+                            paddle.set_point(i + points, (back.x as f32, back.y as f32));
+                        }
+
+                        paddle.set_outline_color(mouse_tracker.outline_color());
+                        paddle.set_fill_color(Color::TRANSPARENT);
+                        paddle.set_outline_thickness(outline);
+                        window.draw(&paddle);
+                    }
+                    (mode, _) => {
+                        let radius = match mode {
+                            MouseMode::Push => tracker.radius as f32 - outline,
+                            MouseMode::Attract => mouse_settings.radius as f32,
+                        };
+                        mouse_tracker.set_radius(radius);
+                        mouse_tracker.set_origin((radius, radius));
+                        mouse_tracker.set_position((tracker.pos.x as _, tracker.pos.y as _));
+                        window.draw(&mouse_tracker);
+                    }
+                }
+            });
+
+        let window_size = window.size();
         let di = sfegui
             .run(&mut window, |_rw, ctx| {
                 egui::Window::new("Settings")
@@ -294,8 +769,1476 @@ pub fn run() {
                             ui.checkbox(&mut fps_limited, "Limit FPS");
                             ui.add_enabled(fps_limited, egui::Slider::new(&mut fps_limit, 1..=1000))
                         });
+
+                        egui::ComboBox::from_label("Color")
+                            .selected_text(format!("{color_mode:?}"))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut color_mode, ColorMode::Random, "Random");
+                                ui.selectable_value(
+                                    &mut color_mode,
+                                    ColorMode::ChargeSign,
+                                    "ChargeSign",
+                                );
+                                ui.selectable_value(
+                                    &mut color_mode,
+                                    ColorMode::Temperature,
+                                    "Temperature",
+                                );
+                            });
+
+                        {
+                            let mut mouse = resources.get_mut::<MouseSettings>().unwrap();
+
+                            egui::ComboBox::from_label("Mouse (hold space)")
+                                .selected_text(format!("{:?}", mouse.mode))
+                                .show_ui(ui, |ui| {
+                                    for mode in [MouseMode::Push, MouseMode::Attract] {
+                                        ui.selectable_value(
+                                            &mut mouse.mode,
+                                            mode,
+                                            format!("{mode:?}"),
+                                        );
+                                    }
+                                });
+
+                            if mouse.mode == MouseMode::Attract {
+                                ui.add(
+                                    egui::Slider::new(&mut mouse.strength, -5000.0..=5000.0)
+                                        .text("Attraction"),
+                                );
+                                ui.add(
+                                    egui::Slider::new(&mut mouse.radius, 10.0..=500.0)
+                                        .text("Attraction radius"),
+                                );
+                                falloff_picker(ui, "mouse falloff", &mut mouse.falloff);
+                            } else {
+                                let mut entry = world.entry(tracker_entity).unwrap();
+                                let tracker = entry.get_component_mut::<MouseTracker>().unwrap();
+                                let is_paddle = matches!(tracker.shape, MouseShape::Paddle { .. });
+
+                                egui::ComboBox::from_id_salt("mouse shape")
+                                    .selected_text(if is_paddle { "Paddle" } else { "Circle" })
+                                    .show_ui(ui, |ui| {
+                                        if ui.selectable_label(!is_paddle, "Circle").clicked() {
+                                            tracker.shape = MouseShape::Circle;
+                                        }
+
+                                        if ui.selectable_label(is_paddle, "Paddle").clicked()
+                                            && !is_paddle
+                                        {
+                                            tracker.shape = MouseShape::Paddle {
+                                                length: 200.0,
+                                                angle: 0.0,
+                                            };
+                                        }
+                                    });
+
+                                ui.add(
+                                    egui::Slider::new(&mut tracker.radius, 5.0..=200.0)
+                                        .text("Pusher radius"),
+                                );
+
+                                if let MouseShape::Paddle { length, angle } = &mut tracker.shape {
+                                    ui.add(
+                                        egui::Slider::new(length, 10.0..=800.0)
+                                            .text("Paddle length"),
+                                    );
+
+                                    let mut degrees = angle.to_degrees();
+
+                                    if ui
+                                        .add(
+                                            egui::Slider::new(&mut degrees, -180.0..=180.0)
+                                                .suffix("°")
+                                                .text("Paddle angle"),
+                                        )
+                                        .changed()
+                                    {
+                                        *angle = degrees.to_radians();
+                                    }
+                                }
+
+                                ui.add(
+                                    egui::Slider::new(&mut mouse.restitution, 0.0..=1.0)
+                                        .text("Restitution"),
+                                );
+                            }
+                        }
+
+                        {
+                            let mut grab = resources.get_mut::<GrabSettings>().unwrap();
+
+                            ui.checkbox(&mut grab.enabled, "Grab and throw (left click)");
+
+                            if grab.enabled {
+                                ui.add(
+                                    egui::Slider::new(&mut grab.stiffness, 10.0..=2000.0)
+                                        .logarithmic(true)
+                                        .text("Grab stiffness"),
+                                );
+                                ui.add(
+                                    egui::Slider::new(&mut grab.damping, 0.0..=100.0)
+                                        .text("Grab damping"),
+                                );
+                            }
+                        }
+
+                        egui::ComboBox::from_label("Selection (shift + drag)")
+                            .selected_text(format!("{selection_shape:?}"))
+                            .show_ui(ui, |ui| {
+                                for shape in [SelectionShape::Box, SelectionShape::Lasso] {
+                                    ui.selectable_value(
+                                        &mut selection_shape,
+                                        shape,
+                                        format!("{shape:?}"),
+                                    );
+                                }
+                            });
+
+                        ui.separator();
+
+                        {
+                            let mut mode = resources.get_mut::<InteractionMode>().unwrap();
+
+                            egui::ComboBox::from_label("Interaction")
+                                .selected_text(format!("{:?}", *mode))
+                                .show_ui(ui, |ui| {
+                                    for m in [
+                                        InteractionMode::HardSpheres,
+                                        InteractionMode::PairPotential,
+                                        InteractionMode::Sph,
+                                        InteractionMode::PositionBasedFluid,
+                                    ] {
+                                        ui.selectable_value(&mut *mode, m, format!("{m:?}"));
+                                    }
+                                });
+
+                            let mut response = resources.get_mut::<CollisionResponse>().unwrap();
+
+                            ui.add_enabled_ui(*mode == InteractionMode::HardSpheres, |ui| {
+                                egui::ComboBox::from_label("Collision response")
+                                    .selected_text(format!("{:?}", *response))
+                                    .show_ui(ui, |ui| {
+                                        for r in
+                                            [CollisionResponse::Elastic, CollisionResponse::Merge]
+                                        {
+                                            ui.selectable_value(
+                                                &mut *response,
+                                                r,
+                                                format!("{r:?}"),
+                                            );
+                                        }
+                                    });
+                            });
+                        }
+
+                        ui.collapsing("Fragmentation", |ui| {
+                            let mut fragmentation =
+                                resources.get_mut::<FragmentationSettings>().unwrap();
+
+                            ui.checkbox(&mut fragmentation.enabled, "Shatter on hard impacts");
+                            ui.add(
+                                egui::Slider::new(&mut fragmentation.threshold, 1.0..=5000.0)
+                                    .logarithmic(true)
+                                    .text("Impact speed threshold"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut fragmentation.fragments, 2..=8)
+                                    .text("Fragments"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut fragmentation.spread, 0.0..=1.0)
+                                    .text("Spread"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut fragmentation.min_radius, 0.5..=10.0)
+                                    .text("Minimum fragment radius"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut fragmentation.max_particles, 100..=20_000)
+                                    .text("Particle cap"),
+                            );
+                        });
+
+                        ui.collapsing("Pair potential", |ui| {
+                            let mut pair = resources.get_mut::<PairPotentialSettings>().unwrap();
+
+                            ui.horizontal(|ui| {
+                                for potential in
+                                    [PairPotential::LENNARD_JONES, PairPotential::MORSE]
+                                {
+                                    let selected = std::mem::discriminant(&pair.potential)
+                                        == std::mem::discriminant(&potential);
+
+                                    if ui
+                                        .selectable_label(selected, format!("{potential:?}"))
+                                        .clicked()
+                                    {
+                                        pair.potential = potential;
+                                    }
+                                }
+                            });
+
+                            match &mut pair.potential {
+                                PairPotential::LennardJones { epsilon, sigma } => {
+                                    ui.add(
+                                        egui::Slider::new(epsilon, 1.0..=100_000.0)
+                                            .logarithmic(true)
+                                            .text("ε"),
+                                    );
+                                    ui.add(egui::Slider::new(sigma, 1.0..=50.0).text("σ"));
+                                }
+
+                                PairPotential::Morse {
+                                    depth,
+                                    width,
+                                    equilibrium,
+                                } => {
+                                    ui.add(
+                                        egui::Slider::new(depth, 1.0..=100_000.0)
+                                            .logarithmic(true)
+                                            .text("Depth"),
+                                    );
+                                    ui.add(egui::Slider::new(width, 0.01..=2.0).text("Width"));
+                                    ui.add(
+                                        egui::Slider::new(equilibrium, 1.0..=50.0)
+                                            .text("Equilibrium distance"),
+                                    );
+                                }
+
+                                PairPotential::Custom(_) => {
+                                    ui.label("User-defined potential");
+                                }
+                            }
+
+                            ui.label("Table with r,energy,force lines");
+                            ui.text_edit_singleline(&mut potential_path);
+
+                            if ui.button("Load table").clicked() {
+                                potential_status = match std::fs::read_to_string(&potential_path)
+                                    .map_err(|error| error.to_string())
+                                    .and_then(|text| PairPotential::tabulated(&text))
+                                {
+                                    Ok(potential) => {
+                                        pair.potential = potential;
+                                        format!("Loaded {potential_path}")
+                                    }
+                                    Err(error) => error,
+                                };
+                            }
+
+                            if !potential_status.is_empty() {
+                                ui.label(&potential_status);
+                            }
+
+                            ui.add(egui::Slider::new(&mut pair.cutoff, 1.0..=200.0).text("Cutoff"));
+                            ui.checkbox(&mut pair.shift, "Shift energy to zero at cutoff");
+                        });
+
+                        ui.collapsing("SPH", |ui| {
+                            let mut sph = resources.get_mut::<SphSettings>().unwrap();
+
+                            ui.add(
+                                egui::Slider::new(&mut sph.smoothing_radius, 5.0..=100.0)
+                                    .text("Smoothing radius"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut sph.rest_density, 0.0001..=0.1)
+                                    .logarithmic(true)
+                                    .text("Rest density"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut sph.stiffness, 1.0..=1_000_000.0)
+                                    .logarithmic(true)
+                                    .text("Stiffness"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut sph.viscosity, 0.0..=100.0)
+                                    .logarithmic(true)
+                                    .text("Viscosity"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut sph.surface_tension, 0.0..=10_000.0)
+                                    .logarithmic(true)
+                                    .text("Surface tension"),
+                            );
+                        });
+
+                        ui.collapsing("Position based fluid", |ui| {
+                            let mut pbf = resources.get_mut::<PbfSettings>().unwrap();
+
+                            ui.add(
+                                egui::Slider::new(&mut pbf.smoothing_radius, 5.0..=100.0)
+                                    .text("Smoothing radius"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut pbf.rest_density, 0.0001..=0.1)
+                                    .logarithmic(true)
+                                    .text("Rest density"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut pbf.iterations, 1..=20).text("Iterations"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut pbf.relaxation, 1e-9..=1e-3)
+                                    .logarithmic(true)
+                                    .text("Relaxation"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut pbf.tensile_correction, 0.0..=0.1)
+                                    .text("Tensile correction"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut pbf.viscosity, 0.0..=1.0)
+                                    .text("XSPH viscosity"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut pbf.vorticity_confinement, 0.0..=100.0)
+                                    .text("Vorticity confinement"),
+                            );
+                        });
+
+                        ui.collapsing("Structures", |ui| {
+                            ui.checkbox(&mut rigid_links, "Rigid links");
+                            ui.add_enabled(
+                                !rigid_links,
+                                egui::Slider::new(&mut spring_stiffness, 1.0..=10_000.0)
+                                    .logarithmic(true)
+                                    .text("Spring stiffness"),
+                            );
+                            ui.add_enabled(
+                                !rigid_links,
+                                egui::Slider::new(&mut spring_damping, 0.0..=100.0)
+                                    .text("Spring damping"),
+                            );
+
+                            let mut constraints =
+                                resources.get_mut::<ConstraintSettings>().unwrap();
+                            ui.add_enabled(
+                                rigid_links,
+                                egui::Slider::new(&mut constraints.iterations, 1..=50)
+                                    .text("Solver iterations"),
+                            );
+
+                            ui.add(egui::Slider::new(&mut structure_size, 2..=50).text("Size"));
+
+                            let kind = if rigid_links {
+                                LinkKind::Rigid
+                            } else {
+                                LinkKind::Spring {
+                                    stiffness: spring_stiffness,
+                                    damping: spring_damping,
+                                }
+                            };
+
+                            let template = ParticleTemplate {
+                                radius: particle_radius as f64,
+                                mass: 1.0,
+                                charge: 0.0,
+                                color: random_color(),
+                                temperature: spawn_temperature,
+                                specific_heat,
+                            };
+
+                            let spacing = 3.0 * template.radius;
+                            let centre = DVec2::new(window_size.x as f64, 0.0) / 2.0;
+                            let width = spacing * (structure_size - 1) as f64;
+
+                            ui.horizontal(|ui| {
+                                let spawned = if ui.button("Rope").clicked() {
+                                    spawn::chain(
+                                        &mut world,
+                                        &template,
+                                        centre + DVec2::new(0.0, 50.0),
+                                        centre + DVec2::new(width, 50.0),
+                                        structure_size - 1,
+                                        kind,
+                                        true,
+                                    )
+                                } else if ui.button("Cloth").clicked() {
+                                    spawn::grid(
+                                        &mut world,
+                                        &template,
+                                        centre + DVec2::new(-width / 2.0, 50.0),
+                                        (structure_size, structure_size),
+                                        spacing,
+                                        kind,
+                                        true,
+                                    )
+                                } else if ui.button("Lattice").clicked() {
+                                    spawn::grid(
+                                        &mut world,
+                                        &template,
+                                        centre + DVec2::new(-width / 2.0, 50.0),
+                                        (structure_size, structure_size),
+                                        spacing,
+                                        kind,
+                                        false,
+                                    )
+                                } else {
+                                    vec![]
+                                };
+
+                                num_particles += spawned.len() as u32;
+                            });
+
+                            ui.separator();
+
+                            ui.add(
+                                egui::Slider::new(&mut soft_pressure, 0.0..=1000.0)
+                                    .text("Soft body pressure"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut soft_shape_stiffness, 0.0..=1.0)
+                                    .text("Shape matching stiffness"),
+                            );
+
+                            ui.horizontal(|ui| {
+                                let centre = centre + DVec2::new(0.0, 200.0);
+
+                                let (members, outline) = if ui.button("Ring").clicked() {
+                                    let members = spawn::ring(
+                                        &mut world,
+                                        &template,
+                                        centre,
+                                        width / 4.0,
+                                        structure_size * 2,
+                                        kind,
+                                    );
+
+                                    (members.clone(), members)
+                                } else if ui.button("Blob").clicked() {
+                                    let size = (structure_size / 2).max(2);
+                                    let width = spacing * (size - 1) as f64;
+                                    let members = spawn::grid(
+                                        &mut world,
+                                        &template,
+                                        centre - DVec2::splat(width / 2.0),
+                                        (size, size),
+                                        spacing,
+                                        kind,
+                                        false,
+                                    );
+                                    let outline = spawn::grid_outline(&members, (size, size));
+
+                                    (members, outline)
+                                } else {
+                                    return;
+                                };
+
+                                num_particles += members.len() as u32;
+                                spawn::soft_body(
+                                    &mut world,
+                                    members,
+                                    outline,
+                                    soft_pressure,
+                                    soft_shape_stiffness,
+                                );
+                            });
+
+                            ui.separator();
+
+                            ui.horizontal(|ui| {
+                                let r = template.radius;
+                                let centre = centre + DVec2::new(0.0, 100.0);
+
+                                let parts = if ui.button("Rod").clicked() {
+                                    (0..structure_size)
+                                        .map(|i| (DVec2::new(i as f64 * 2.0 * r, 0.0), r))
+                                        .collect::<Vec<_>>()
+                                } else if ui.button("Dumbbell").clicked() {
+                                    let bar = (-4..=4).map(|i| (DVec2::new(i as f64 * r, 0.0), r));
+                                    [(DVec2::new(-8.0 * r, 0.0), 3.0 * r)]
+                                        .into_iter()
+                                        .chain(bar)
+                                        .chain([(DVec2::new(8.0 * r, 0.0), 3.0 * r)])
+                                        .collect()
+                                } else if ui.button("Grain").clicked() {
+                                    (0..thread_rng().gen_range(3..=6))
+                                        .map(|i| {
+                                            let angle =
+                                                thread_rng().gen_range(0.0..std::f64::consts::TAU);
+                                            let distance = if i == 0 { 0.0 } else { 1.5 * r };
+                                            (DVec2::from_angle(angle) * distance, r)
+                                        })
+                                        .collect()
+                                } else {
+                                    return;
+                                };
+
+                                num_particles +=
+                                    spawn::rigid_body(&mut world, &template, centre, &parts).len()
+                                        as u32;
+                            });
+                        });
+
+                        ui.collapsing("Collision layers", |ui| {
+                            ui.label("Spawned particles");
+
+                            egui::ComboBox::from_label("Layer")
+                                .selected_text(format!(
+                                    "{}",
+                                    spawn_group.layer.trailing_zeros() + 1
+                                ))
+                                .show_ui(ui, |ui| {
+                                    for layer in 0..8 {
+                                        ui.selectable_value(
+                                            &mut spawn_group.layer,
+                                            1 << layer,
+                                            format!("{}", layer + 1),
+                                        );
+                                    }
+                                });
+
+                            ui.label("Collides with");
+                            ui.horizontal_wrapped(|ui| {
+                                let mut toggle = |ui: &mut egui::Ui, bit: u32, name: String| {
+                                    let mut enabled = spawn_group.mask & bit != 0;
+
+                                    if ui.checkbox(&mut enabled, name).changed() {
+                                        spawn_group.mask ^= bit;
+                                    }
+                                };
+
+                                for layer in 0..8 {
+                                    toggle(ui, 1 << layer, format!("{}", layer + 1));
+                                }
+
+                                toggle(ui, CollisionGroup::WALLS, "Walls".to_string());
+                                toggle(ui, CollisionGroup::MOUSE, "Mouse".to_string());
+                            });
+
+                            ui.horizontal(|ui| {
+                                if ui.button("Default").clicked() {
+                                    spawn_group = CollisionGroup::default();
+                                }
+
+                                // tracers pass through everything but the walls
+                                if ui.button("Tracer").clicked() {
+                                    spawn_group = CollisionGroup {
+                                        layer: 1 << 1,
+                                        mask: CollisionGroup::WALLS,
+                                    };
+                                }
+                            });
+                        });
+
+                        ui.collapsing("Emitters", |ui| {
+                            if ui.button("Add emitter").clicked() {
+                                world.push((Emitter {
+                                    position: DVec2::new(window_size.x as f64 / 2.0, 50.0),
+                                    ..Default::default()
+                                },));
+                            }
+
+                            let mut removed = vec![];
+
+                            for (i, (entity, emitter)) in <(Entity, &mut Emitter)>::query()
+                                .iter_mut(&mut world)
+                                .enumerate()
+                            {
+                                ui.separator();
+                                ui.horizontal(|ui| {
+                                    ui.label(format!(
+                                        "Emitter {} ({} emitted)",
+                                        i + 1,
+                                        emitter.emitted
+                                    ));
+
+                                    if ui.small_button("Remove").clicked() {
+                                        removed.push(*entity);
+                                    }
+                                });
+
+                                let angle = |ui: &mut egui::Ui, radians: &mut f64| {
+                                    let mut degrees = radians.to_degrees();
+
+                                    if ui
+                                        .add(egui::DragValue::new(&mut degrees).suffix("°"))
+                                        .changed()
+                                    {
+                                        *radians = degrees.to_radians();
+                                    }
+                                };
+
+                                let interval =
+                                    |ui: &mut egui::Ui, interval: &mut Interval, name| {
+                                        ui.horizontal(|ui| {
+                                            ui.label(name);
+                                            ui.add(egui::DragValue::new(&mut interval.min));
+                                            ui.label("to");
+                                            ui.add(egui::DragValue::new(&mut interval.max));
+                                        });
+                                    };
+
+                                ui.horizontal(|ui| {
+                                    ui.add(
+                                        egui::DragValue::new(&mut emitter.position.x).prefix("x: "),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(&mut emitter.position.y).prefix("y: "),
+                                    );
+                                    ui.label("Direction");
+                                    angle(ui, &mut emitter.direction);
+                                    ui.label("Spread");
+                                    angle(ui, &mut emitter.spread);
+                                });
+
+                                ui.add(
+                                    egui::Slider::new(&mut emitter.rate, 0.0..=500.0)
+                                        .text("Particles per second"),
+                                );
+                                interval(ui, &mut emitter.speed, "Speed");
+                                interval(ui, &mut emitter.radius, "Radius");
+                                interval(ui, &mut emitter.mass, "Mass");
+
+                                ui.horizontal(|ui| {
+                                    let mut limited = emitter.max_count.is_some();
+                                    ui.checkbox(&mut limited, "Max count");
+
+                                    match (limited, &mut emitter.max_count) {
+                                        (true, Some(max)) => {
+                                            ui.add(egui::DragValue::new(max));
+                                        }
+                                        (true, max) => *max = Some(emitter.emitted + 100),
+                                        (false, max) => *max = None,
+                                    }
+                                });
+
+                                ui.horizontal(|ui| {
+                                    let mut limited = emitter.lifetime.is_some();
+                                    ui.checkbox(&mut limited, "Lifetime");
+
+                                    match (limited, &mut emitter.lifetime) {
+                                        (true, Some(lifetime)) => {
+                                            ui.add(
+                                                egui::DragValue::new(lifetime)
+                                                    .range(0.0..=f64::INFINITY)
+                                                    .suffix("s"),
+                                            );
+                                        }
+                                        (true, lifetime) => *lifetime = Some(5.0),
+                                        (false, lifetime) => *lifetime = None,
+                                    }
+                                });
+                            }
+
+                            for entity in removed {
+                                world.remove(entity);
+                            }
+                        });
+
+                        ui.collapsing("Scene file", |ui| {
+                            ui.text_edit_singleline(&mut scene_path);
+
+                            ui.horizontal(|ui| {
+                                if ui.button("Load").clicked() {
+                                    scene_status = match scene::load(&scene_path) {
+                                        Ok(objects) => {
+                                            scene::spawn(&mut world, &objects);
+                                            format!("Loaded {} objects", objects.len())
+                                        }
+                                        Err(error) => format!("Error: {error}"),
+                                    };
+                                }
+
+                                if ui.button("Save").clicked() {
+                                    let objects = scene::collect(&world);
+
+                                    scene_status = match scene::save(&scene_path, &objects) {
+                                        Ok(()) => format!("Saved {} objects", objects.len()),
+                                        Err(error) => format!("Error: {error}"),
+                                    };
+                                }
+                            });
+
+                            if !scene_status.is_empty() {
+                                ui.label(&scene_status);
+                            }
+                        });
+
+                        let centre = DVec2::new(window_size.x as f64, window_size.y as f64) / 2.0;
+
+                        ui.collapsing("Sensors", |ui| {
+                            if let Some(region) = new_region(ui, centre) {
+                                world.push((Sensor::new(region),));
+                            }
+
+                            let mut removed = vec![];
+
+                            for (i, (entity, sensor)) in <(Entity, &mut Sensor)>::query()
+                                .iter_mut(&mut world)
+                                .enumerate()
+                            {
+                                ui.separator();
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("Sensor {}", i + 1));
+
+                                    if ui.small_button("Remove").clicked() {
+                                        removed.push(*entity);
+                                    }
+                                });
+
+                                edit_region(ui, &mut sensor.region);
+
+                                ui.label(format!(
+                                    "Inside: {}, entered: {}, exited: {}",
+                                    sensor.inside.len(),
+                                    sensor.total_entered,
+                                    sensor.total_exited
+                                ));
+                            }
+
+                            for entity in removed {
+                                world.remove(entity);
+                            }
+                        });
+
+                        ui.collapsing("Force fields", |ui| {
+                            ui.horizontal(|ui| {
+                                let radial = |strength| ForceField::Radial {
+                                    centre,
+                                    radius: 150.0,
+                                    strength,
+                                    falloff: Falloff::Linear,
+                                };
+
+                                let field = if ui.button("Wind").clicked() {
+                                    ForceField::Wind {
+                                        region: Region::Rectangle {
+                                            min: centre - DVec2::new(200.0, 50.0),
+                                            max: centre + DVec2::new(200.0, 50.0),
+                                        },
+                                        acceleration: DVec2::new(200.0, 0.0),
+                                    }
+                                } else if ui.button("Vortex").clicked() {
+                                    ForceField::Vortex {
+                                        centre,
+                                        radius: 150.0,
+                                        strength: 300.0,
+                                        falloff: Falloff::Linear,
+                                    }
+                                } else if ui.button("Attractor").clicked() {
+                                    radial(500.0)
+                                } else if ui.button("Repulsor").clicked() {
+                                    radial(-500.0)
+                                } else {
+                                    return;
+                                };
+
+                                world.push((field,));
+                            });
+
+                            let mut removed = vec![];
+
+                            for (i, (entity, field)) in <(Entity, &mut ForceField)>::query()
+                                .iter_mut(&mut world)
+                                .enumerate()
+                            {
+                                ui.separator();
+                                ui.horizontal(|ui| {
+                                    let name = match field {
+                                        ForceField::Wind { .. } => "Wind",
+                                        ForceField::Vortex { .. } => "Vortex",
+                                        ForceField::Radial { .. } => "Radial",
+                                    };
+                                    ui.label(format!("{name} {}", i + 1));
+
+                                    if ui.small_button("Remove").clicked() {
+                                        removed.push(*entity);
+                                    }
+                                });
+
+                                match field {
+                                    ForceField::Wind {
+                                        region,
+                                        acceleration,
+                                    } => {
+                                        edit_region(ui, region);
+                                        ui.horizontal(|ui| {
+                                            ui.label("Acceleration");
+                                            ui.add(egui::DragValue::new(&mut acceleration.x));
+                                            ui.add(egui::DragValue::new(&mut acceleration.y));
+                                        });
+                                    }
+                                    ForceField::Vortex {
+                                        centre,
+                                        radius,
+                                        strength,
+                                        falloff,
+                                    }
+                                    | ForceField::Radial {
+                                        centre,
+                                        radius,
+                                        strength,
+                                        falloff,
+                                    } => {
+                                        edit_circle(ui, centre, radius);
+                                        ui.add(
+                                            egui::Slider::new(strength, -5000.0..=5000.0)
+                                                .text("Strength"),
+                                        );
+                                        falloff_picker(ui, *entity, falloff);
+                                    }
+                                }
+                            }
+
+                            for entity in removed {
+                                world.remove(entity);
+                            }
+                        });
+
+                        ui.collapsing("Sinks", |ui| {
+                            if let Some(region) = new_region(ui, centre) {
+                                world.push((Sink::new(region),));
+                            }
+
+                            let mut removed = vec![];
+
+                            for (i, (entity, sink)) in <(Entity, &mut Sink)>::query()
+                                .iter_mut(&mut world)
+                                .enumerate()
+                            {
+                                ui.separator();
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("Sink {}", i + 1));
+
+                                    if ui.small_button("Remove").clicked() {
+                                        removed.push(*entity);
+                                    }
+                                });
+
+                                edit_region(ui, &mut sink.region);
+
+                                ui.label(format!(
+                                    "Removed: {} particles, {:.1} mass",
+                                    sink.removed, sink.removed_mass
+                                ));
+                            }
+
+                            for entity in removed {
+                                world.remove(entity);
+                            }
+                        });
+
+                        ui.collapsing("Flow field", |ui| {
+                            let mut field = resources.get_mut::<flow::VectorField>().unwrap();
+
+                            ui.horizontal(|ui| {
+                                ui.text_edit_singleline(&mut flow_path);
+
+                                if ui.button("Load").clicked() {
+                                    flow_status = match flow::VectorField::load(&flow_path) {
+                                        Ok(loaded) => {
+                                            *field = flow::VectorField {
+                                                enabled: true,
+                                                coupling: field.coupling,
+                                                ..loaded
+                                            };
+
+                                            format!("Loaded {}x{} grid", field.columns, field.rows)
+                                        }
+                                        Err(error) => format!("Error: {error}"),
+                                    };
+                                }
+                            });
+
+                            if !flow_status.is_empty() {
+                                ui.label(&flow_status);
+                            }
+
+                            ui.checkbox(&mut field.enabled, "Drag towards the flow");
+                            ui.add(
+                                egui::Slider::new(&mut field.coupling, 0.0..=100.0)
+                                    .logarithmic(true)
+                                    .text("Coupling"),
+                            );
+                            ui.checkbox(&mut draw_flow, "Draw flow");
+                        });
+
+                        ui.collapsing("Neighbour lists", |ui| {
+                            let mut list = resources.get_mut::<NeighbourList>().unwrap();
+
+                            ui.checkbox(&mut list.enabled, "Cache neighbour lists");
+                            ui.add(egui::Slider::new(&mut list.skin, 0.0..=50.0).text("Skin"));
+                        });
+
+                        ui.collapsing("Drag", |ui| {
+                            let mut drag = resources.get_mut::<DragSettings>().unwrap();
+
+                            ui.checkbox(&mut drag.enabled, "Enabled");
+                            ui.add(
+                                egui::Slider::new(&mut drag.linear, 0.0..=10.0)
+                                    .logarithmic(true)
+                                    .text("Linear"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut drag.quadratic, 0.0..=1.0)
+                                    .logarithmic(true)
+                                    .text("Quadratic"),
+                            );
+                            ui.checkbox(&mut drag.use_cross_section, "Scale by cross-section");
+                        });
+
+                        ui.collapsing("N-body gravity", |ui| {
+                            let mut nbody = resources.get_mut::<NBodySettings>().unwrap();
+
+                            ui.checkbox(&mut nbody.enabled, "Enabled");
+                            ui.add(
+                                egui::Slider::new(&mut nbody.g, 0.0..=100_000.0)
+                                    .logarithmic(true)
+                                    .text("G"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut nbody.softening, 0.0..=50.0)
+                                    .text("Softening"),
+                            );
+                            ui.checkbox(&mut nbody.exact, "Exact O(n²) summation");
+                            ui.add_enabled(
+                                !nbody.exact,
+                                egui::Slider::new(&mut nbody.theta, 0.0..=2.0).text("θ"),
+                            );
+                            ui.add_enabled(
+                                !nbody.exact,
+                                egui::Checkbox::new(
+                                    &mut nbody.measure_error,
+                                    "Measure error against exact",
+                                ),
+                            );
+                        });
+
+                        ui.collapsing("Electrostatics", |ui| {
+                            let mut electrostatics =
+                                resources.get_mut::<ElectrostaticsSettings>().unwrap();
+
+                            ui.checkbox(&mut electrostatics.enabled, "Enabled");
+                            ui.add(
+                                egui::Slider::new(&mut electrostatics.k, 0.0..=1_000_000.0)
+                                    .logarithmic(true)
+                                    .text("k"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut electrostatics.softening, 0.0..=20.0)
+                                    .text("Softening"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut electrostatics.cutoff, 10.0..=500.0)
+                                    .text("Cutoff radius"),
+                            );
+
+                            ui.separator();
+
+                            egui::ComboBox::from_label("Spawned charges")
+                                .selected_text(format!("{charge_distribution:?}"))
+                                .show_ui(ui, |ui| {
+                                    for distribution in [
+                                        ChargeDistribution::Neutral,
+                                        ChargeDistribution::Fixed,
+                                        ChargeDistribution::Alternating,
+                                        ChargeDistribution::RandomSign,
+                                        ChargeDistribution::Uniform,
+                                    ] {
+                                        ui.selectable_value(
+                                            &mut charge_distribution,
+                                            distribution,
+                                            format!("{distribution:?}"),
+                                        );
+                                    }
+                                });
+                            ui.add(
+                                egui::Slider::new(&mut charge_magnitude, 0.1..=10.0)
+                                    .text("Charge magnitude"),
+                            );
+                        });
+
+                        ui.collapsing("Temperature", |ui| {
+                            let mut heat = resources.get_mut::<HeatSettings>().unwrap();
+
+                            ui.checkbox(&mut heat.enabled, "Heat conduction");
+                            ui.add(
+                                egui::Slider::new(&mut heat.conductance, 0.0..=100.0)
+                                    .logarithmic(true)
+                                    .text("Conductance"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut spawn_temperature, 0.0..=2000.0)
+                                    .text("Spawn temperature"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut specific_heat, 0.01..=100.0)
+                                    .logarithmic(true)
+                                    .text("Specific heat"),
+                            );
+                            ui.add(
+                                egui::Slider::new(&mut max_temperature, 1.0..=5000.0)
+                                    .text("Hottest drawn temperature"),
+                            );
+
+                            ui.separator();
+
+                            ui.label("Heat baths");
+                            ui.add(
+                                egui::Slider::new(&mut heat.bath_conductance, 0.0..=100.0)
+                                    .logarithmic(true)
+                                    .text("Bath conductance"),
+                            );
+
+                            for wall in [Wall::Left, Wall::Right, Wall::Top, Wall::Bottom] {
+                                let bath = &mut heat.baths[wall as usize];
+
+                                ui.horizontal(|ui| {
+                                    let mut enabled = bath.is_some();
+                                    ui.checkbox(&mut enabled, format!("{wall:?}"));
+
+                                    match (enabled, bath.as_mut()) {
+                                        (true, Some(temperature)) => {
+                                            ui.add(egui::Slider::new(temperature, 0.0..=2000.0));
+                                        }
+                                        (true, None) => *bath = Some(spawn_temperature),
+                                        (false, _) => *bath = None,
+                                    }
+                                });
+                            }
+                        });
+
+                        ui.collapsing("Chemistry", |ui| {
+                            let mut chemistry = resources.get_mut::<Chemistry>().unwrap();
+                            let chemistry = &mut *chemistry;
+                            let names = chemistry
+                                .species
+                                .iter()
+                                .map(|species| species.name.clone())
+                                .collect::<Vec<_>>();
+
+                            ui.checkbox(&mut chemistry.enabled, "Reactions");
+
+                            egui::ComboBox::from_label("Spawned species")
+                                .selected_text(match spawn_species {
+                                    Some(species) => names[species].as_str(),
+                                    None => "None",
+                                })
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut spawn_species, None, "None");
+                                    for (i, name) in names.iter().enumerate() {
+                                        ui.selectable_value(&mut spawn_species, Some(i), name);
+                                    }
+                                });
+
+                            ui.separator();
+
+                            for species in &mut chemistry.species {
+                                ui.horizontal(|ui| {
+                                    let Color { r, g, b, .. } = species.color;
+                                    let mut color = [r, g, b];
+
+                                    ui.color_edit_button_srgb(&mut color);
+                                    species.color = Color::rgb(color[0], color[1], color[2]);
+
+                                    ui.label(&species.name);
+                                    ui.add(
+                                        egui::DragValue::new(&mut species.radius)
+                                            .range(1.0..=50.0)
+                                            .prefix("r: "),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(&mut species.mass)
+                                            .range(0.01..=1000.0)
+                                            .speed(0.1)
+                                            .prefix("m: "),
+                                    );
+                                });
+                            }
+
+                            if names.len() < 26 && ui.button("Add species").clicked() {
+                                chemistry.species.push(SpeciesDefinition {
+                                    name: ((b'A' + names.len() as u8) as char).to_string(),
+                                    radius: particle_radius as f64,
+                                    mass: 1.0,
+                                    color: random_color(),
+                                });
+                            }
+
+                            ui.separator();
+
+                            let species_picker =
+                                |ui: &mut egui::Ui, id: egui::Id, species: &mut usize| {
+                                    egui::ComboBox::from_id_salt(id)
+                                        .width(40.0)
+                                        .selected_text(names[*species].as_str())
+                                        .show_ui(ui, |ui| {
+                                            for (i, name) in names.iter().enumerate() {
+                                                ui.selectable_value(species, i, name);
+                                            }
+                                        });
+                                };
+
+                            let mut removed = None;
+
+                            for (i, rule) in chemistry.reactions.iter_mut().enumerate() {
+                                ui.horizontal(|ui| {
+                                    species_picker(
+                                        ui,
+                                        egui::Id::new((i, "a")),
+                                        &mut rule.reactants[0],
+                                    );
+                                    ui.label("+");
+                                    species_picker(
+                                        ui,
+                                        egui::Id::new((i, "b")),
+                                        &mut rule.reactants[1],
+                                    );
+                                    ui.label("->");
+
+                                    for (j, product) in rule.products.iter_mut().enumerate() {
+                                        species_picker(ui, egui::Id::new((i, j)), product);
+                                    }
+
+                                    if ui.small_button("+").clicked() {
+                                        rule.products.push(0);
+                                    }
+                                    if !rule.products.is_empty() && ui.small_button("-").clicked() {
+                                        rule.products.pop();
+                                    }
+                                    if ui.small_button("Remove").clicked() {
+                                        removed = Some(i);
+                                    }
+                                });
+
+                                ui.add(
+                                    egui::Slider::new(&mut rule.probability, 0.0..=1.0)
+                                        .text("Probability"),
+                                );
+                                ui.add(
+                                    egui::Slider::new(&mut rule.activation_energy, 0.0..=10_000.0)
+                                        .logarithmic(true)
+                                        .text("Activation energy"),
+                                );
+                            }
+
+                            if let Some(i) = removed {
+                                chemistry.reactions.remove(i);
+                            }
+
+                            if ui.button("Add reaction").clicked() {
+                                chemistry.reactions.push(ReactionRule {
+                                    reactants: [0, 0],
+                                    products: vec![0],
+                                    probability: 1.0,
+                                    activation_energy: 0.0,
+                                });
+                            }
+                        });
                     });
 
+                let selected = <Entity>::query()
+                    .filter(component::<Selected>())
+                    .iter(&world)
+                    .copied()
+                    .collect::<Vec<_>>();
+
+                if !selected.is_empty() {
+                    egui::Window::new("Selection")
+                        .resizable(false)
+                        .show(ctx, |ui| {
+                            ui.label(format!("{} particles selected", selected.len()));
+                            ui.separator();
+
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut bulk_velocity.x).prefix("vx: "));
+                                ui.add(egui::DragValue::new(&mut bulk_velocity.y).prefix("vy: "));
+
+                                if ui.button("Set velocity").clicked() {
+                                    for entity in &selected {
+                                        let mut entry = world.entry(*entity).unwrap();
+
+                                        if let Ok(Velocity(vel)) =
+                                            entry.get_component_mut::<Velocity>()
+                                        {
+                                            *vel = bulk_velocity;
+                                        }
+                                    }
+                                }
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::DragValue::new(&mut bulk_mass)
+                                        .range(0.01..=f64::INFINITY)
+                                        .prefix("m: "),
+                                );
+
+                                if ui.button("Set mass").clicked() {
+                                    for entity in &selected {
+                                        let mut entry = world.entry(*entity).unwrap();
+
+                                        if let Ok(Mass(mass)) = entry.get_component_mut::<Mass>() {
+                                            *mass = bulk_mass;
+                                        }
+                                    }
+                                }
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::DragValue::new(&mut bulk_radius)
+                                        .range(1.0..=100.0)
+                                        .prefix("r: "),
+                                );
+
+                                if ui.button("Set radius").clicked() {
+                                    for entity in &selected {
+                                        let mut entry = world.entry(*entity).unwrap();
+
+                                        if let Ok(shape) = entry.get_component_mut::<ShapeInfo>() {
+                                            shape.radius = bulk_radius;
+                                        }
+                                    }
+                                }
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.color_edit_button_srgb(&mut bulk_color);
+
+                                if ui.button("Set color").clicked() {
+                                    for entity in &selected {
+                                        let mut entry = world.entry(*entity).unwrap();
+
+                                        if let Ok(shape) = entry.get_component_mut::<ShapeInfo>() {
+                                            shape.color = Color::rgb(
+                                                bulk_color[0],
+                                                bulk_color[1],
+                                                bulk_color[2],
+                                            );
+                                        }
+                                    }
+                                }
+                            });
+
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::DragValue::new(&mut bulk_drag.linear)
+                                        .range(0.0..=f64::INFINITY)
+                                        .speed(0.01)
+                                        .prefix("linear: "),
+                                );
+                                ui.add(
+                                    egui::DragValue::new(&mut bulk_drag.quadratic)
+                                        .range(0.0..=f64::INFINITY)
+                                        .speed(0.0001)
+                                        .prefix("quadratic: "),
+                                );
+
+                                if ui.button("Set drag").clicked() {
+                                    for entity in &selected {
+                                        world.entry(*entity).unwrap().add_component(bulk_drag);
+                                    }
+                                }
+
+                                // back to the global drag settings
+                                if ui.button("Clear drag").clicked() {
+                                    for entity in &selected {
+                                        world.entry(*entity).unwrap().remove_component::<Drag>();
+                                    }
+                                }
+                            });
+
+                            ui.separator();
+
+                            ui.horizontal(|ui| {
+                                if ui.button("Pin").clicked() {
+                                    for entity in &selected {
+                                        let mut entry = world.entry(*entity).unwrap();
+
+                                        if let Ok(Position(pos)) = entry.get_component::<Position>()
+                                        {
+                                            let anchor = Pinned(*pos);
+                                            entry.add_component(anchor);
+                                        }
+                                    }
+                                }
+
+                                if ui.button("Unpin").clicked() {
+                                    for entity in &selected {
+                                        world.entry(*entity).unwrap().remove_component::<Pinned>();
+                                    }
+                                }
+
+                                if ui.button("Delete").clicked() {
+                                    for entity in &selected {
+                                        world.remove(*entity);
+                                    }
+                                }
+
+                                if ui.button("Deselect").clicked() {
+                                    for entity in &selected {
+                                        world
+                                            .entry(*entity)
+                                            .unwrap()
+                                            .remove_component::<Selected>();
+                                    }
+                                }
+                            });
+                        });
+                }
+
+                // the particle was removed
+                if inspected.is_some_and(|entity| !world.contains(entity)) {
+                    inspected = None;
+                }
+
+                if let Some(entity) = inspected {
+                    let mut open = true;
+
+                    egui::Window::new("Particle")
+                        .open(&mut open)
+                        .resizable(false)
+                        .show(ctx, |ui| {
+                            let neighbours = {
+                                let entry = world.entry_ref(entity).unwrap();
+                                let Position(pos) = entry.get_component::<Position>().unwrap();
+                                let ShapeInfo { radius, .. } =
+                                    entry.get_component::<ShapeInfo>().unwrap();
+                                let Id(id) = entry.get_component::<Id>().unwrap();
+
+                                let mut neighbours = resources
+                                    .get::<quadtree::QuadTree<usize>>()
+                                    .unwrap()
+                                    .query(*pos, radius + inspect_range);
+                                neighbours.retain(|other| other != id);
+                                neighbours.sort_unstable();
+                                neighbours
+                            };
+
+                            let mut entry = world.entry(entity).unwrap();
+                            let id = entry.get_component::<Id>().unwrap().0;
+                            ui.label(format!("Id: {id}"));
+
+                            let mass = if let Ok(Mass(mass)) = entry.get_component_mut::<Mass>() {
+                                ui.horizontal(|ui| {
+                                    ui.label("Mass");
+                                    ui.add(egui::DragValue::new(mass).range(0.01..=f64::INFINITY));
+                                });
+                                *mass
+                            } else {
+                                0.0
+                            };
+
+                            if let Ok(Position(pos)) = entry.get_component_mut::<Position>() {
+                                ui.horizontal(|ui| {
+                                    ui.label("Position");
+                                    ui.add(egui::DragValue::new(&mut pos.x).prefix("x: "));
+                                    ui.add(egui::DragValue::new(&mut pos.y).prefix("y: "));
+                                });
+                            }
+
+                            let speed =
+                                if let Ok(Velocity(vel)) = entry.get_component_mut::<Velocity>() {
+                                    ui.horizontal(|ui| {
+                                        ui.label("Velocity");
+                                        ui.add(egui::DragValue::new(&mut vel.x).prefix("x: "));
+                                        ui.add(egui::DragValue::new(&mut vel.y).prefix("y: "));
+                                    });
+                                    vel.length()
+                                } else {
+                                    0.0
+                                };
+
+                            if let Ok(shape) = entry.get_component_mut::<ShapeInfo>() {
+                                ui.horizontal(|ui| {
+                                    let Color { r, g, b, .. } = shape.color;
+                                    let mut color = [r, g, b];
+
+                                    ui.label("Shape");
+                                    ui.add(
+                                        egui::DragValue::new(&mut shape.radius)
+                                            .range(1.0..=100.0)
+                                            .prefix("r: "),
+                                    );
+                                    ui.color_edit_button_srgb(&mut color);
+                                    shape.color = Color::rgb(color[0], color[1], color[2]);
+                                });
+                            }
+
+                            ui.horizontal(|ui| {
+                                let mut overridden = entry.get_component::<Drag>().is_ok();
+
+                                if ui.checkbox(&mut overridden, "Own drag").changed() {
+                                    if overridden {
+                                        let settings = resources.get::<DragSettings>().unwrap();
+                                        entry.add_component(Drag {
+                                            linear: settings.linear,
+                                            quadratic: settings.quadratic,
+                                        });
+                                    } else {
+                                        entry.remove_component::<Drag>();
+                                    }
+                                }
+
+                                if let Ok(Drag { linear, quadratic }) =
+                                    entry.get_component_mut::<Drag>()
+                                {
+                                    ui.add(
+                                        egui::DragValue::new(linear)
+                                            .range(0.0..=f64::INFINITY)
+                                            .speed(0.01)
+                                            .prefix("linear: "),
+                                    );
+                                    ui.add(
+                                        egui::DragValue::new(quadratic)
+                                            .range(0.0..=f64::INFINITY)
+                                            .speed(0.0001)
+                                            .prefix("quadratic: "),
+                                    );
+                                }
+                            });
+
+                            ui.label(format!("Speed: {speed:.1}"));
+                            ui.label(format!("Kinetic energy: {:.1}", 0.5 * mass * speed * speed));
+                            ui.separator();
+
+                            ui.horizontal(|ui| {
+                                ui.label(format!("Neighbours: {}", neighbours.len()));
+                                ui.add(
+                                    egui::DragValue::new(&mut inspect_range)
+                                        .range(0.0..=500.0)
+                                        .prefix("within "),
+                                );
+                            });
+
+                            if !neighbours.is_empty() {
+                                let ids = neighbours
+                                    .iter()
+                                    .take(20)
+                                    .map(|id| id.to_string())
+                                    .collect::<Vec<_>>();
+                                let more = if neighbours.len() > ids.len() {
+                                    ", ..."
+                                } else {
+                                    ""
+                                };
+
+                                ui.label(format!("Ids: {}{more}", ids.join(", ")));
+                            }
+
+                            ui.separator();
+
+                            speed_history.push_back(speed);
+                            if speed_history.len() > 300 {
+                                speed_history.pop_front();
+                            }
+
+                            ui.label("Speed");
+                            plot::line_plot(ui, 80.0, &[(
+                                egui::Color32::from_rgb(120, 255, 255),
+                                speed_history.iter().copied().collect(),
+                            )]);
+                        });
+
+                    if !open {
+                        inspected = None;
+                    }
+                }
+
                 egui::Window::new("Info")
                     .collapsible(true)
                     .open(&mut show_info)
@@ -310,8 +2253,83 @@ pub fn run() {
                             (resources.get::<CollisionDetectionTime>().unwrap().0 as f64 / 1e6),
                         ));
                         ui.label(format!("Quadtree time: {qt_build_time:.2}ms"));
+
+                        let events = resources.get::<CollisionEvents>().unwrap();
+                        ui.label(format!(
+                            "Contacts: {}, wall hits: {}",
+                            events.particles.len(),
+                            events.walls.len()
+                        ));
+
+                        let list = resources.get::<NeighbourList>().unwrap();
+                        if list.enabled {
+                            ui.label(format!("Neighbour list rebuilds: {}", list.rebuilds));
+                        }
+
+                        let nbody = resources.get::<NBodyStats>().unwrap();
+                        if resources.get::<NBodySettings>().unwrap().enabled {
+                            ui.label(format!("Gravity time: {:.2}ms", nbody.time as f64 / 1e6));
+                        }
+
+                        if let Some(error) = nbody.mean_relative_error {
+                            ui.label(format!("Barnes-Hut mean error: {:.3}%", error * 100.0));
+                        }
+                        ui.separator();
+
+                        let energy = resources.get::<EnergyStats>().unwrap();
+                        ui.label(format!("Kinetic energy: {:.1}", energy.kinetic));
+                        ui.label(format!("Potential energy: {:.1}", energy.potential));
+                        ui.label(format!(
+                            "Total energy: {:.1}",
+                            energy.kinetic + energy.potential
+                        ));
+
+                        let temperatures = <&Temperature>::query()
+                            .iter(&world)
+                            .map(|t| t.0)
+                            .collect::<Vec<_>>();
+                        if !temperatures.is_empty() {
+                            ui.label(format!(
+                                "Mean temperature: {:.1}K",
+                                temperatures.iter().sum::<f64>() / temperatures.len() as f64
+                            ));
+                        }
+
+                        let chemistry = resources.get::<Chemistry>().unwrap();
+
+                        if chemistry.enabled {
+                            let history = resources.get::<SpeciesHistory>().unwrap();
+
+                            ui.separator();
+                            ui.label(format!(
+                                "Species counts (last {:.0}s)",
+                                history.samples.len() as f64 * history.interval
+                            ));
+
+                            let series = chemistry
+                                .species
+                                .iter()
+                                .enumerate()
+                                .map(|(i, species)| {
+                                    let Color { r, g, b, .. } = species.color;
+                                    let counts = history
+                                        .samples
+                                        .iter()
+                                        .map(|counts| counts.get(i).copied().unwrap_or(0) as f64)
+                                        .collect();
+
+                                    (egui::Color32::from_rgb(r, g, b), counts)
+                                })
+                                .collect::<Vec<_>>();
+
+                            plot::line_plot(ui, 100.0, &series);
+                        }
+
                         ui.separator();
-                        ui.label(format!("Particles: {num_particles}"));
+                        ui.label(format!(
+                            "Particles: {}",
+                            <&Id>::query().iter(&world).count()
+                        ));
                     });
             })
             .unwrap();
@@ -322,11 +2340,66 @@ pub fn run() {
     }
 }
 
-fn id() -> usize {
-    static mut INDEX: usize = 0;
+fn random_color() -> Color {
+    Color::rgb(
+        thread_rng().gen_range(0..=255),
+        thread_rng().gen_range(0..=255),
+        thread_rng().gen_range(0..=255),
+    )
+}
 
-    let ret = unsafe { INDEX };
-    unsafe { INDEX += 1 };
+/// Buttons adding a rectangle or a circle around `centre`
+fn new_region(ui: &mut egui::Ui, centre: DVec2) -> Option<Region> {
+    ui.horizontal(|ui| {
+        if ui.button("Add rectangle").clicked() {
+            Some(Region::Rectangle {
+                min: centre - DVec2::splat(50.0),
+                max: centre + DVec2::splat(50.0),
+            })
+        } else if ui.button("Add circle").clicked() {
+            Some(Region::Circle {
+                centre,
+                radius: 50.0,
+            })
+        } else {
+            None
+        }
+    })
+    .inner
+}
+
+fn edit_region(ui: &mut egui::Ui, region: &mut Region) {
+    match region {
+        Region::Rectangle { min, max } => {
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut min.x).prefix("x: "));
+                ui.add(egui::DragValue::new(&mut min.y).prefix("y: "));
+                ui.add(egui::DragValue::new(&mut max.x).prefix("to x: "));
+                ui.add(egui::DragValue::new(&mut max.y).prefix("to y: "));
+            });
+        }
+        Region::Circle { centre, radius } => edit_circle(ui, centre, radius),
+    }
+}
+
+fn edit_circle(ui: &mut egui::Ui, centre: &mut DVec2, radius: &mut f64) {
+    ui.horizontal(|ui| {
+        ui.add(egui::DragValue::new(&mut centre.x).prefix("x: "));
+        ui.add(egui::DragValue::new(&mut centre.y).prefix("y: "));
+        ui.add(
+            egui::DragValue::new(radius)
+                .range(0.0..=f64::INFINITY)
+                .prefix("r: "),
+        );
+    });
+}
 
-    ret
+fn falloff_picker(ui: &mut egui::Ui, id: impl std::hash::Hash, falloff: &mut Falloff) {
+    egui::ComboBox::from_id_salt(id)
+        .selected_text(format!("{falloff:?} falloff"))
+        .show_ui(ui, |ui| {
+            for f in [Falloff::None, Falloff::Linear, Falloff::Quadratic] {
+                ui.selectable_value(falloff, f, format!("{f:?}"));
+            }
+        });
 }
@@ -0,0 +1,232 @@
+//! External flow fields given on a regular grid, particles are dragged
+//! towards the local flow velocity so they act as tracers.
+//!
+//! CSV files have one `x,y,u,v` line per grid point in any order, a header line is skipped.
+//! Binary files start with the magic `VFLD`, followed by little endian
+//! `u32 columns, u32 rows, f64 origin x, origin y, spacing x, spacing y`
+//! and `columns * rows` pairs of `f64 u, v` in row major order.
+
+use std::fmt;
+use std::path::Path;
+
+use glam::DVec2;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VectorField {
+    pub enabled: bool,
+    /// Rate at which particle velocities relax towards the flow, per second
+    pub coupling: f64,
+    /// Position of the first grid point
+    pub origin: DVec2,
+    pub spacing: DVec2,
+    pub columns: usize,
+    pub rows: usize,
+    /// Flow velocities in row major order
+    pub velocities: Vec<DVec2>,
+}
+
+impl Default for VectorField {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            coupling: 2.0,
+            origin: DVec2::ZERO,
+            spacing: DVec2::ONE,
+            columns: 0,
+            rows: 0,
+            velocities: vec![],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+    Format(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(f, "{error}"),
+            LoadError::Parse { line, message } => write!(f, "line {line}: {message}"),
+            LoadError::Format(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(error: std::io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+
+impl VectorField {
+    /// Picks the format by the file extension, anything but `.csv` is read as binary
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+
+        if path.extension().is_some_and(|extension| extension == "csv") {
+            Self::from_csv(&std::fs::read_to_string(path)?)
+        } else {
+            Self::from_binary(&std::fs::read(path)?)
+        }
+    }
+
+    pub fn from_csv(text: &str) -> Result<Self, LoadError> {
+        let mut points = vec![];
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let values = line
+                .split(',')
+                .map(|value| value.trim().parse::<f64>())
+                .collect::<Result<Vec<_>, _>>();
+
+            match values.as_deref() {
+                Ok(&[x, y, u, v]) => points.push((DVec2::new(x, y), DVec2::new(u, v))),
+                Err(_) if i == 0 => continue,
+                _ => {
+                    return Err(LoadError::Parse {
+                        line: i + 1,
+                        message: "expected x,y,u,v".to_string(),
+                    });
+                }
+            }
+        }
+
+        let axis = |coordinate: fn(&DVec2) -> f64| {
+            let mut values = points
+                .iter()
+                .map(|(p, _)| coordinate(p))
+                .collect::<Vec<_>>();
+            values.sort_by(f64::total_cmp);
+            values.dedup();
+            values
+        };
+
+        let xs = axis(|p| p.x);
+        let ys = axis(|p| p.y);
+
+        if xs.len() < 2 || ys.len() < 2 || xs.len() * ys.len() != points.len() {
+            return Err(LoadError::Format(format!(
+                "{} points don't form a regular grid",
+                points.len()
+            )));
+        }
+
+        let spacing = DVec2::new(xs[1] - xs[0], ys[1] - ys[0]);
+
+        // the sampling assumes every cell has the same size
+        let uniform = |values: &[f64], spacing: f64| {
+            values.iter().enumerate().all(|(i, value)| {
+                (value - (values[0] + i as f64 * spacing)).abs() <= 1e-6 * spacing.max(1.0)
+            })
+        };
+
+        if !uniform(&xs, spacing.x) || !uniform(&ys, spacing.y) {
+            return Err(LoadError::Format("grid isn't uniformly spaced".to_string()));
+        }
+
+        let mut field = Self {
+            origin: DVec2::new(xs[0], ys[0]),
+            spacing,
+            columns: xs.len(),
+            rows: ys.len(),
+            velocities: vec![DVec2::ZERO; points.len()],
+            ..Default::default()
+        };
+
+        for (position, velocity) in points {
+            let column = xs.partition_point(|x| *x < position.x);
+            let row = ys.partition_point(|y| *y < position.y);
+            field.velocities[row * field.columns + column] = velocity;
+        }
+
+        Ok(field)
+    }
+
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, LoadError> {
+        let truncated = || LoadError::Format("truncated vector field".to_string());
+
+        let Some(body) = bytes.strip_prefix(b"VFLD") else {
+            return Err(LoadError::Format("missing VFLD header".to_string()));
+        };
+
+        let u32_at = |offset: usize| {
+            body.get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize)
+                .ok_or_else(truncated)
+        };
+        let f64_at = |offset: usize| {
+            body.get(offset..offset + 8)
+                .map(|b| f64::from_le_bytes(b.try_into().unwrap()))
+                .ok_or_else(truncated)
+        };
+
+        let columns = u32_at(0)?;
+        let rows = u32_at(4)?;
+
+        if columns < 2 || rows < 2 {
+            return Err(LoadError::Format(format!(
+                "{columns}x{rows} grid is too small"
+            )));
+        }
+
+        let origin = DVec2::new(f64_at(8)?, f64_at(16)?);
+        let spacing = DVec2::new(f64_at(24)?, f64_at(32)?);
+
+        // `sample` would turn these into NaN velocities
+        if !origin.is_finite() {
+            return Err(LoadError::Format(format!("invalid grid origin {origin}")));
+        }
+
+        if !spacing.is_finite() || spacing.cmple(DVec2::ZERO).any() {
+            return Err(LoadError::Format(format!("invalid grid spacing {spacing}")));
+        }
+
+        let velocities = (0..columns * rows)
+            .map(|i| Ok(DVec2::new(f64_at(40 + 16 * i)?, f64_at(48 + 16 * i)?)))
+            .collect::<Result<Vec<_>, LoadError>>()?;
+
+        Ok(Self {
+            origin,
+            spacing,
+            columns,
+            rows,
+            velocities,
+            ..Default::default()
+        })
+    }
+
+    /// Bilinearly interpolated flow velocity, `None` outside the grid
+    pub fn sample(&self, pos: DVec2) -> Option<DVec2> {
+        if self.velocities.is_empty() {
+            return None;
+        }
+
+        let grid = (pos - self.origin) / self.spacing;
+        let max = DVec2::new((self.columns - 1) as f64, (self.rows - 1) as f64);
+
+        if grid.cmplt(DVec2::ZERO).any() || grid.cmpgt(max).any() {
+            return None;
+        }
+
+        // the last cell also covers the far edge
+        let cell = grid.floor().min(max - 1.0);
+        let t = grid - cell;
+        let (column, row) = (cell.x as usize, cell.y as usize);
+        let at = |column: usize, row: usize| self.velocities[row * self.columns + column];
+
+        let top = at(column, row).lerp(at(column + 1, row), t.x);
+        let bottom = at(column, row + 1).lerp(at(column + 1, row + 1), t.x);
+
+        Some(top.lerp(bottom, t.y))
+    }
+}
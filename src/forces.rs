@@ -0,0 +1,258 @@
+//! Pairwise force laws shared by the force systems
+
+use glam::DVec2;
+
+/// Acceleration of a body at `position` caused by `mass` at `other`.
+/// `softening` keeps the force finite for overlapping bodies.
+pub fn gravitational_acceleration(
+    position: DVec2,
+    other: DVec2,
+    mass: f64,
+    g: f64,
+    softening: f64,
+) -> DVec2 {
+    let r = other - position;
+    let distance_squared = r.length_squared() + softening * softening;
+
+    if distance_squared == 0.0 {
+        return DVec2::ZERO;
+    }
+
+    g * mass * r / (distance_squared * distance_squared.sqrt())
+}
+
+/// Exact O(n²) gravitational accelerations, used as the reference for Barnes-Hut
+pub fn direct_gravity(bodies: &[(DVec2, f64)], g: f64, softening: f64) -> Vec<DVec2> {
+    bodies
+        .iter()
+        .enumerate()
+        .map(|(i, (position, _))| {
+            bodies
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| i != *j)
+                .map(|(_, (other, mass))| {
+                    gravitational_acceleration(*position, *other, *mass, g, softening)
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Coulomb force on the charge `q1` at `position` caused by `q2` at `other`,
+/// positive for like charges (repulsion)
+pub fn coulomb_force(
+    position: DVec2,
+    other: DVec2,
+    q1: f64,
+    q2: f64,
+    k: f64,
+    softening: f64,
+) -> DVec2 {
+    let r = position - other;
+    let distance_squared = r.length_squared() + softening * softening;
+
+    if distance_squared == 0.0 {
+        return DVec2::ZERO;
+    }
+
+    k * q1 * q2 * r / (distance_squared * distance_squared.sqrt())
+}
+
+/// Soft pair potential used instead of hard sphere collisions
+#[derive(Clone)]
+pub enum PairPotential {
+    LennardJones {
+        epsilon: f64,
+        sigma: f64,
+    },
+    Morse {
+        /// well depth
+        depth: f64,
+        /// well width parameter, larger means narrower
+        width: f64,
+        equilibrium: f64,
+    },
+    /// Takes the distance and returns `(energy, force)`,
+    /// the force being positive when repulsive
+    Custom(std::sync::Arc<dyn Fn(f64) -> (f64, f64) + Send + Sync>),
+}
+
+impl std::fmt::Debug for PairPotential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LennardJones { .. } => write!(f, "Lennard-Jones"),
+            Self::Morse { .. } => write!(f, "Morse"),
+            Self::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+impl PairPotential {
+    pub const LENNARD_JONES: Self = Self::LennardJones {
+        epsilon: 1000.0,
+        sigma: 10.0,
+    };
+
+    pub const MORSE: Self = Self::Morse {
+        depth: 1000.0,
+        width: 0.3,
+        equilibrium: 12.0,
+    };
+
+    /// User-defined potential from a table with one `r,energy,force` line per distance,
+    /// a header line and `#` comments are skipped. Values are interpolated linearly,
+    /// clamped below the first distance and zero beyond the last one.
+    pub fn tabulated(text: &str) -> Result<Self, String> {
+        let mut table = vec![];
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let values = line
+                .split(',')
+                .map(|value| value.trim().parse::<f64>())
+                .collect::<Result<Vec<_>, _>>();
+
+            match values.as_deref() {
+                Ok(&[r, e, f]) if r.is_finite() && e.is_finite() && f.is_finite() => {
+                    table.push((r, e, f))
+                }
+                Err(_) if table.is_empty() && i == 0 => continue,
+                _ => return Err(format!("line {}: expected r,energy,force", i + 1)),
+            }
+        }
+
+        if table.len() < 2 {
+            return Err("the table needs at least two distances".to_string());
+        }
+
+        if table.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err("distances must be increasing".to_string());
+        }
+
+        Ok(Self::Custom(std::sync::Arc::new(move |r| {
+            let last = table[table.len() - 1];
+
+            if r > last.0 {
+                return (0.0, 0.0);
+            }
+
+            let i = table
+                .partition_point(|(r2, ..)| *r2 <= r)
+                .clamp(1, table.len() - 1);
+            let ((r1, e1, f1), (r2, e2, f2)) = (table[i - 1], table[i]);
+            let t = ((r - r1) / (r2 - r1)).clamp(0.0, 1.0);
+
+            (e1 + (e2 - e1) * t, f1 + (f2 - f1) * t)
+        })))
+    }
+
+    /// Returns `(energy, force)` at distance `r`, the force is positive when repulsive.
+    /// Built-in potentials are clamped close to the core so that
+    /// overlapping particles don't receive infinite forces.
+    pub fn evaluate(&self, r: f64) -> (f64, f64) {
+        match self {
+            Self::LennardJones { epsilon, sigma } => {
+                let r = r.max(0.8 * sigma);
+                let sr6 = (sigma / r).powi(6);
+                let sr12 = sr6 * sr6;
+
+                (
+                    4.0 * epsilon * (sr12 - sr6),
+                    24.0 * epsilon * (2.0 * sr12 - sr6) / r,
+                )
+            }
+
+            Self::Morse {
+                depth,
+                width,
+                equilibrium,
+            } => {
+                let r = r.max(0.5 * equilibrium);
+                let e = (-width * (r - equilibrium)).exp();
+
+                (
+                    depth * (1.0 - e).powi(2) - depth,
+                    2.0 * depth * width * e * (e - 1.0),
+                )
+            }
+
+            Self::Custom(f) => f(r),
+        }
+    }
+}
+
+/// Signed area, positive when the points wind counter-clockwise (in a y-up system)
+pub fn polygon_area(points: &[DVec2]) -> f64 {
+    0.5 * (0..points.len())
+        .map(|i| points[i].perp_dot(points[(i + 1) % points.len()]))
+        .sum::<f64>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quadtree::QuadTree;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use sfml::graphics::Rect;
+
+    const G: f64 = 1000.0;
+    const SOFTENING: f64 = 1.0;
+
+    fn random_cloud(n: usize) -> Vec<(DVec2, f64)> {
+        let mut rng = StdRng::seed_from_u64(27);
+
+        (0..n)
+            .map(|_| {
+                let position = DVec2::new(rng.gen_range(0.0..500.0), rng.gen_range(0.0..500.0));
+                (position, rng.gen_range(1.0..10.0))
+            })
+            .collect()
+    }
+
+    fn barnes_hut(bodies: &[(DVec2, f64)], theta: f64) -> Vec<DVec2> {
+        let mut tree = QuadTree::new(4, Rect::new(0.0, 0.0, 500.0, 500.0));
+
+        for (i, (position, _)) in bodies.iter().enumerate() {
+            tree.push((*position, 0.0, i));
+        }
+
+        let mass_of = |i: &usize| bodies[*i].1;
+        tree.compute_mass_distribution(&mass_of);
+
+        (0..bodies.len())
+            .map(|i| {
+                tree.gravitational_acceleration(bodies[i].0, &i, theta, G, SOFTENING, &mass_of)
+            })
+            .collect()
+    }
+
+    fn relative_errors(bodies: &[(DVec2, f64)], theta: f64) -> Vec<f64> {
+        direct_gravity(bodies, G, SOFTENING)
+            .iter()
+            .zip(barnes_hut(bodies, theta))
+            .map(|(exact, approximate)| (approximate - *exact).length() / exact.length())
+            .collect()
+    }
+
+    #[test]
+    fn barnes_hut_without_approximation_is_exact() {
+        let errors = relative_errors(&random_cloud(200), 0.0);
+
+        assert!(errors.iter().all(|e| *e < 1e-9), "{errors:?}");
+    }
+
+    #[test]
+    fn barnes_hut_error_is_bounded() {
+        let errors = relative_errors(&random_cloud(200), 0.5);
+        let mean = errors.iter().sum::<f64>() / errors.len() as f64;
+
+        assert!(mean < 0.01, "mean relative error {mean}");
+        assert!(errors.iter().all(|e| *e < 0.1), "{errors:?}");
+    }
+}
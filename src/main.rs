@@ -2,7 +2,14 @@ mod engine;
 
 mod collision;
 mod components;
+mod flow;
+mod forces;
+mod neighbours;
+mod plot;
 mod quadtree;
+mod scene;
+mod spawn;
+mod sph;
 mod systems;
 
 mod renderer;
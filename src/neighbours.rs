@@ -0,0 +1,91 @@
+//! Verlet neighbour lists, cached between frames and only rebuilt
+//! when a particle has moved more than half the skin distance
+
+use std::collections::HashMap;
+
+use glam::DVec2;
+use sfml::graphics::Rect;
+
+use crate::quadtree::QuadTree;
+
+#[derive(Clone, Debug)]
+pub struct NeighbourList {
+    pub enabled: bool,
+    /// Extra distance added to the interaction range, larger
+    /// skins mean longer lists but fewer rebuilds
+    pub skin: f64,
+    /// Interaction range and skin the lists were built with
+    range: f64,
+    built_skin: f64,
+    /// Positions at the time of the last rebuild by particle id
+    reference: HashMap<usize, DVec2>,
+    /// By particle id
+    neighbours: HashMap<usize, Vec<usize>>,
+    pub rebuilds: usize,
+}
+
+impl Default for NeighbourList {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            skin: 10.0,
+            range: 0.0,
+            built_skin: 0.0,
+            reference: HashMap::new(),
+            neighbours: HashMap::new(),
+            rebuilds: 0,
+        }
+    }
+}
+
+impl NeighbourList {
+    /// Particles within `range + skin` of `id` at the time of the last rebuild
+    pub fn neighbours(&self, id: usize) -> &[usize] {
+        self.neighbours.get(&id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// `positions` are the current particle positions with their ids
+    pub fn needs_rebuild(&self, positions: &[(usize, DVec2)], range: f64) -> bool {
+        if range != self.range
+            || self.skin != self.built_skin
+            || positions.len() != self.reference.len()
+        {
+            return true;
+        }
+
+        // the lists only cover displacements within the skin they were built with
+        let max_displacement = 0.5 * self.built_skin;
+
+        positions
+            .iter()
+            .any(|(id, pos)| match self.reference.get(id) {
+                Some(reference) => reference.distance_squared(*pos) > max_displacement.powi(2),
+                None => true,
+            })
+    }
+
+    pub fn rebuild(&mut self, positions: &[(usize, DVec2)], range: f64, boundary: Rect<f64>) {
+        let mut qt = QuadTree::new(8, boundary);
+        for (id, pos) in positions {
+            qt.push((*pos, 0.0, *id));
+        }
+
+        self.reference = positions.iter().copied().collect();
+        self.neighbours = positions
+            .iter()
+            .map(|(id, pos)| {
+                let neighbours = qt
+                    .query(*pos, range + self.skin)
+                    .into_iter()
+                    .filter(|other| other != id)
+                    .collect();
+
+                (*id, neighbours)
+            })
+            .collect();
+
+        self.range = range;
+        self.built_skin = self.skin;
+        self.rebuilds += 1;
+    }
+}
@@ -0,0 +1,57 @@
+//! Small line plots for the egui windows, drawn straight with the painter
+
+use egui_sfml::egui;
+use egui::{Color32, Stroke, Ui};
+
+/// Plots every series against its sample index, the y axis starts at zero
+/// unless a series goes negative
+pub fn line_plot(ui: &mut Ui, height: f32, series: &[(Color32, Vec<f64>)]) {
+    let (response, painter) = ui.allocate_painter(
+        egui::vec2(ui.available_width(), height),
+        egui::Sense::hover(),
+    );
+    let rect = response.rect;
+
+    painter.rect_stroke(rect, 0.0, Stroke::new(1.0, Color32::DARK_GRAY));
+
+    let values = || series.iter().flat_map(|(_, values)| values.iter().copied());
+    let max = values().fold(0.0, f64::max);
+    let min = values().fold(0.0, f64::min);
+    let range = (max - min).max(f64::EPSILON);
+    let samples = series
+        .iter()
+        .map(|(_, values)| values.len())
+        .max()
+        .unwrap_or(0);
+
+    for (color, values) in series {
+        let points = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let x = i as f32 / (samples.max(2) - 1) as f32;
+                let y = ((value - min) / range) as f32;
+
+                egui::pos2(
+                    rect.left() + x * rect.width(),
+                    rect.bottom() - y * rect.height(),
+                )
+            })
+            .collect();
+
+        painter.add(egui::Shape::line(points, Stroke::new(1.5, *color)));
+    }
+
+    let label = |pos, align, value: f64| {
+        painter.text(
+            pos,
+            align,
+            format!("{value:.1}"),
+            egui::FontId::monospace(10.0),
+            Color32::GRAY,
+        );
+    };
+
+    label(rect.left_top(), egui::Align2::LEFT_TOP, max);
+    label(rect.left_bottom(), egui::Align2::LEFT_BOTTOM, min);
+}
@@ -1,6 +1,8 @@
 use sfml::graphics::*;
 use glam::DVec2;
 
+use crate::forces::gravitational_acceleration;
+
 /// Make sure not to put a big object as the generic
 /// type parameter since there's a lot of cloning.
 #[derive(Debug, Clone)]
@@ -9,6 +11,10 @@ pub struct QuadTree<T: Clone> {
     capacity: usize,
     points: Vec<Option<(DVec2, f64, T)>>,
     children: Option<Box<[QuadTree<T>; 4]>>,
+
+    /// Total mass of this node and its children, filled by `compute_mass_distribution`
+    mass: f64,
+    centre_of_mass: DVec2,
 }
 
 impl<T: Clone> QuadTree<T> {
@@ -18,6 +24,8 @@ impl<T: Clone> QuadTree<T> {
             boundary,
             points: Vec::with_capacity(capacity),
             children: None,
+            mass: 0.0,
+            centre_of_mass: DVec2::ZERO,
         }
     }
 
@@ -88,6 +96,96 @@ impl<T: Clone> QuadTree<T> {
         found
     }
 
+    /// Computes the total mass and centre of mass of every node,
+    /// must be called again after pushing new points
+    pub fn compute_mass_distribution(&mut self, mass_of: &impl Fn(&T) -> f64) {
+        let mut mass = 0.0;
+        let mut weighted_position = DVec2::ZERO;
+
+        for (position, _, data) in self.points.iter().flatten() {
+            let m = mass_of(data);
+            mass += m;
+            weighted_position += *position * m;
+        }
+
+        if let Some(children) = &mut self.children {
+            for child in children.iter_mut() {
+                child.compute_mass_distribution(mass_of);
+                mass += child.mass;
+                weighted_position += child.centre_of_mass * child.mass;
+            }
+        }
+
+        self.mass = mass;
+        self.centre_of_mass = if mass > 0.0 {
+            weighted_position / mass
+        } else {
+            self.boundary_centre()
+        };
+    }
+
+    /// Barnes-Hut approximation of the gravitational acceleration at `position`.
+    /// A node is treated as a single body when `size / distance < theta`,
+    /// `theta = 0` gives the exact result. `exclude` is skipped (the body itself).
+    pub fn gravitational_acceleration(
+        &self,
+        position: DVec2,
+        exclude: &T,
+        theta: f64,
+        g: f64,
+        softening: f64,
+        mass_of: &impl Fn(&T) -> f64,
+    ) -> DVec2
+    where
+        T: PartialEq,
+    {
+        if self.mass <= 0.0 {
+            return DVec2::ZERO;
+        }
+
+        let size = self.boundary.width.max(self.boundary.height);
+        let distance = (self.centre_of_mass - position).length();
+
+        // never approximate a node that contains the body itself
+        if !inside_boundary(&self.boundary, &position) && size < theta * distance {
+            return gravitational_acceleration(
+                position,
+                self.centre_of_mass,
+                self.mass,
+                g,
+                softening,
+            );
+        }
+
+        let mut acceleration = self
+            .points
+            .iter()
+            .flatten()
+            .filter(|(_, _, data)| data != exclude)
+            .map(|(other, _, data)| {
+                gravitational_acceleration(position, *other, mass_of(data), g, softening)
+            })
+            .sum::<DVec2>();
+
+        if let Some(children) = &self.children {
+            acceleration += children
+                .iter()
+                .map(|c| {
+                    c.gravitational_acceleration(position, exclude, theta, g, softening, mass_of)
+                })
+                .sum::<DVec2>();
+        }
+
+        acceleration
+    }
+
+    fn boundary_centre(&self) -> DVec2 {
+        DVec2::new(
+            self.boundary.left + self.boundary.width * 0.5,
+            self.boundary.top + self.boundary.height * 0.5,
+        )
+    }
+
     pub fn push(&mut self, point: (DVec2, f64, T)) {
         if !inside_boundary(&self.boundary, &point.0) {
             return;
@@ -1,4 +1,5 @@
 //! Used to pre-render circle images for faster rendering
+//! and to pick the particle colors
 
 use sfml::graphics::*;
 
@@ -18,3 +19,67 @@ pub fn circle(radius: u32, color: Color) -> sfml::cpp::FBox<Image> {
     let texture = render_target.texture();
     texture.copy_to_image().unwrap()
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    /// `ShapeInfo::color`
+    Random,
+    /// Red for positive, blue for negative and grey for neutral particles
+    ChargeSign,
+    /// Black through red and yellow to white as particles heat up
+    Temperature,
+}
+
+/// `max` is the charge magnitude drawn at full intensity
+pub fn charge_color(charge: f64, max: f64) -> Color {
+    if charge == 0.0 {
+        return Color::rgb(128, 128, 128);
+    }
+
+    let intensity = (charge.abs() / max.max(f64::EPSILON)).clamp(0.0, 1.0);
+    let value = (80.0 + 175.0 * intensity) as u8;
+
+    if charge > 0.0 {
+        Color::rgb(value, 40, 40)
+    } else {
+        Color::rgb(40, 80, value)
+    }
+}
+
+/// `max` is the temperature drawn white
+pub fn heat_color(temperature: f64, max: f64) -> Color {
+    let t = 3.0 * (temperature / max.max(f64::EPSILON)).clamp(0.0, 1.0);
+    let channel = |offset: f64| (255.0 * (t - offset).clamp(0.0, 1.0)) as u8;
+
+    Color::rgb(channel(0.0).max(40), channel(1.0), channel(2.0))
+}
+
+pub fn draw_region(
+    target: &mut RenderWindow,
+    region: &crate::components::Region,
+    fill: Color,
+    outline: Color,
+) {
+    use crate::components::Region;
+
+    match *region {
+        Region::Rectangle { min, max } => {
+            let mut rect =
+                RectangleShape::with_size(((max.x - min.x) as f32, (max.y - min.y) as f32).into());
+            rect.set_position((min.x as f32, min.y as f32));
+            rect.set_fill_color(fill);
+            rect.set_outline_color(outline);
+            rect.set_outline_thickness(1.0);
+            target.draw(&rect);
+        }
+        Region::Circle { centre, radius } => {
+            let mut circle = CircleShape::new(radius as f32, 60);
+            circle.set_origin((radius as f32, radius as f32));
+            circle.set_position((centre.x as f32, centre.y as f32));
+            circle.set_fill_color(fill);
+            circle.set_outline_color(outline);
+            circle.set_outline_thickness(1.0);
+            target.draw(&circle);
+        }
+    }
+}
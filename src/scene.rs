@@ -0,0 +1,368 @@
+//! Plain text scene files, one object per line followed by `key=value` pairs:
+//!
+//! ```text
+//! # a fountain
+//! emitter x=800 y=850 direction=-90 spread=20 speed=200..250 rate=30 radius=3..4 lifetime=6
+//! sink shape=rectangle x=0 y=0 width=1600 height=20
+//! sink shape=circle x=800 y=450 radius=30
+//! wind shape=rectangle x=0 y=300 width=400 height=100 ax=200 ay=0
+//! vortex x=800 y=450 radius=200 strength=300 falloff=linear
+//! radial x=400 y=450 radius=150 strength=-500 falloff=quadratic
+//! ```
+//!
+//! Angles are in degrees, `min..max` values are sampled uniformly,
+//! colors are written as `r,g,b` and missing keys keep their defaults.
+
+use std::fmt;
+use std::path::Path;
+
+use glam::DVec2;
+use legion::*;
+use sfml::graphics::Color;
+
+use crate::components::*;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SceneObject {
+    Emitter(Emitter),
+    Sink(Sink),
+    ForceField(ForceField),
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(error) => write!(f, "{error}"),
+            SceneError::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for SceneError {
+    fn from(error: std::io::Error) -> Self {
+        SceneError::Io(error)
+    }
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<SceneObject>, SceneError> {
+    parse(&std::fs::read_to_string(path)?)
+}
+
+pub fn save(path: impl AsRef<Path>, objects: &[SceneObject]) -> Result<(), SceneError> {
+    Ok(std::fs::write(path, write(objects))?)
+}
+
+/// Scene objects currently in the world
+pub fn collect(world: &World) -> Vec<SceneObject> {
+    let mut objects = vec![];
+
+    objects.extend(
+        <&Emitter>::query()
+            .iter(world)
+            .map(|emitter| SceneObject::Emitter(*emitter)),
+    );
+    objects.extend(
+        <&Sink>::query()
+            .iter(world)
+            .map(|sink| SceneObject::Sink(*sink)),
+    );
+    objects.extend(
+        <&ForceField>::query()
+            .iter(world)
+            .map(|field| SceneObject::ForceField(*field)),
+    );
+
+    objects
+}
+
+pub fn spawn(world: &mut World, objects: &[SceneObject]) {
+    for object in objects {
+        match object {
+            SceneObject::Emitter(emitter) => world.push((*emitter,)),
+            SceneObject::Sink(sink) => world.push((*sink,)),
+            SceneObject::ForceField(field) => world.push((*field,)),
+        };
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<SceneObject>, SceneError> {
+    let mut objects = vec![];
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut words = line.split_whitespace();
+
+        let Some(kind) = words.next() else {
+            continue;
+        };
+
+        let pairs = words
+            .map(|word| {
+                word.split_once('=')
+                    .ok_or(format!("expected key=value, found `{word}`"))
+            })
+            .collect::<Result<Vec<_>, _>>();
+
+        let object = pairs.and_then(|pairs| match kind {
+            "emitter" => parse_emitter(&pairs).map(SceneObject::Emitter),
+            "sink" => parse_region(&pairs).map(|region| SceneObject::Sink(Sink::new(region))),
+            "wind" | "vortex" | "radial" => {
+                parse_force_field(kind, &pairs).map(SceneObject::ForceField)
+            }
+            _ => Err(format!("unknown object `{kind}`")),
+        });
+
+        objects.push(object.map_err(|message| SceneError::Parse {
+            line: i + 1,
+            message,
+        })?);
+    }
+
+    Ok(objects)
+}
+
+pub fn write(objects: &[SceneObject]) -> String {
+    let mut text = String::new();
+
+    for object in objects {
+        let words = match object {
+            SceneObject::Emitter(emitter) => {
+                let mut words = vec![
+                    "emitter".to_string(),
+                    format!("x={}", emitter.position.x),
+                    format!("y={}", emitter.position.y),
+                    format!("direction={}", emitter.direction.to_degrees()),
+                    format!("spread={}", emitter.spread.to_degrees()),
+                    format!("speed={}", interval(emitter.speed)),
+                    format!("rate={}", emitter.rate),
+                    format!("radius={}", interval(emitter.radius)),
+                    format!("mass={}", interval(emitter.mass)),
+                    format!("color={}", color(emitter.color)),
+                    format!("temperature={}", emitter.temperature),
+                ];
+
+                if let Some(max) = emitter.max_count {
+                    words.push(format!("max={max}"));
+                }
+
+                if let Some(lifetime) = emitter.lifetime {
+                    words.push(format!("lifetime={lifetime}"));
+                }
+
+                words
+            }
+            SceneObject::Sink(sink) => [vec!["sink".to_string()], region(&sink.region)].concat(),
+            SceneObject::ForceField(ForceField::Wind {
+                region: r,
+                acceleration,
+            }) => [vec!["wind".to_string()], region(r), vec![
+                format!("ax={}", acceleration.x),
+                format!("ay={}", acceleration.y),
+            ]]
+            .concat(),
+            SceneObject::ForceField(ForceField::Vortex {
+                centre,
+                radius,
+                strength,
+                falloff,
+            }) => circular_field("vortex", *centre, *radius, *strength, *falloff),
+            SceneObject::ForceField(ForceField::Radial {
+                centre,
+                radius,
+                strength,
+                falloff,
+            }) => circular_field("radial", *centre, *radius, *strength, *falloff),
+        };
+
+        text += &words.join(" ");
+        text += "\n";
+    }
+
+    text
+}
+
+fn parse_emitter(pairs: &[(&str, &str)]) -> Result<Emitter, String> {
+    let mut emitter = Emitter::default();
+
+    for &(key, value) in pairs {
+        match key {
+            "x" => emitter.position.x = number(value)?,
+            "y" => emitter.position.y = number(value)?,
+            "direction" => emitter.direction = number(value)?.to_radians(),
+            "spread" => emitter.spread = number(value)?.to_radians(),
+            "speed" => emitter.speed = parse_interval(value)?,
+            "rate" => emitter.rate = number(value)?,
+            "radius" => emitter.radius = parse_interval(value)?,
+            "mass" => emitter.mass = parse_interval(value)?,
+            "color" => emitter.color = parse_color(value)?,
+            "temperature" => emitter.temperature = number(value)?,
+            "max" => emitter.max_count = Some(number(value)? as usize),
+            "lifetime" => emitter.lifetime = Some(number(value)?),
+            _ => return Err(format!("unknown emitter key `{key}`")),
+        }
+    }
+
+    Ok(emitter)
+}
+
+fn parse_force_field(kind: &str, pairs: &[(&str, &str)]) -> Result<ForceField, String> {
+    let mut acceleration = DVec2::ZERO;
+    let (mut centre, mut radius, mut strength) = (DVec2::ZERO, 100.0, 100.0);
+    let mut falloff = Falloff::Linear;
+    let mut region_pairs = vec![];
+
+    for &(key, value) in pairs {
+        match key {
+            "ax" => acceleration.x = number(value)?,
+            "ay" => acceleration.y = number(value)?,
+            "x" if kind != "wind" => centre.x = number(value)?,
+            "y" if kind != "wind" => centre.y = number(value)?,
+            "radius" if kind != "wind" => radius = number(value)?,
+            "strength" => strength = number(value)?,
+            "falloff" => {
+                falloff = match value {
+                    "none" => Falloff::None,
+                    "linear" => Falloff::Linear,
+                    "quadratic" => Falloff::Quadratic,
+                    _ => return Err(format!("unknown falloff `{value}`")),
+                }
+            }
+            _ if kind == "wind" => region_pairs.push((key, value)),
+            _ => return Err(format!("unknown {kind} key `{key}`")),
+        }
+    }
+
+    Ok(match kind {
+        "wind" => ForceField::Wind {
+            region: parse_region(&region_pairs)?,
+            acceleration,
+        },
+        "vortex" => ForceField::Vortex {
+            centre,
+            radius,
+            strength,
+            falloff,
+        },
+        _ => ForceField::Radial {
+            centre,
+            radius,
+            strength,
+            falloff,
+        },
+    })
+}
+
+fn parse_region(pairs: &[(&str, &str)]) -> Result<Region, String> {
+    let mut shape = "rectangle";
+    let (mut x, mut y, mut width, mut height, mut radius) = (0.0, 0.0, 100.0, 100.0, 50.0);
+
+    for &(key, value) in pairs {
+        match key {
+            "shape" => shape = value,
+            "x" => x = number(value)?,
+            "y" => y = number(value)?,
+            "width" => width = number(value)?,
+            "height" => height = number(value)?,
+            "radius" => radius = number(value)?,
+            _ => return Err(format!("unknown region key `{key}`")),
+        }
+    }
+
+    match shape {
+        "rectangle" => Ok(Region::Rectangle {
+            min: DVec2::new(x, y),
+            max: DVec2::new(x + width, y + height),
+        }),
+        "circle" => Ok(Region::Circle {
+            centre: DVec2::new(x, y),
+            radius,
+        }),
+        _ => Err(format!("unknown shape `{shape}`")),
+    }
+}
+
+fn number(value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .map_err(|_| format!("`{value}` is not a number"))
+}
+
+fn parse_interval(value: &str) -> Result<Interval, String> {
+    match value.split_once("..") {
+        Some((min, max)) => Ok(Interval {
+            min: number(min)?,
+            max: number(max)?,
+        }),
+        None => Ok(Interval::constant(number(value)?)),
+    }
+}
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    let channels = value
+        .split(',')
+        .map(|channel| {
+            channel
+                .parse::<u8>()
+                .map_err(|_| format!("`{channel}` is not a color channel"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match channels[..] {
+        [r, g, b] => Ok(Color::rgb(r, g, b)),
+        [r, g, b, a] => Ok(Color::rgba(r, g, b, a)),
+        _ => Err(format!("expected r,g,b, found `{value}`")),
+    }
+}
+
+fn interval(interval: Interval) -> String {
+    if interval.min == interval.max {
+        format!("{}", interval.min)
+    } else {
+        format!("{}..{}", interval.min, interval.max)
+    }
+}
+
+fn region(region: &Region) -> Vec<String> {
+    match *region {
+        Region::Rectangle { min, max } => vec![
+            "shape=rectangle".to_string(),
+            format!("x={}", min.x),
+            format!("y={}", min.y),
+            format!("width={}", max.x - min.x),
+            format!("height={}", max.y - min.y),
+        ],
+        Region::Circle { centre, radius } => vec![
+            "shape=circle".to_string(),
+            format!("x={}", centre.x),
+            format!("y={}", centre.y),
+            format!("radius={radius}"),
+        ],
+    }
+}
+
+fn circular_field(
+    kind: &str,
+    centre: DVec2,
+    radius: f64,
+    strength: f64,
+    falloff: Falloff,
+) -> Vec<String> {
+    vec![
+        kind.to_string(),
+        format!("x={}", centre.x),
+        format!("y={}", centre.y),
+        format!("radius={radius}"),
+        format!("strength={strength}"),
+        format!("falloff={}", format!("{falloff:?}").to_lowercase()),
+    ]
+}
+
+fn color(color: Color) -> String {
+    format!("{},{},{}", color.r, color.g, color.b)
+}
@@ -0,0 +1,338 @@
+//! Spawning particles and the structures built out of them
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use glam::DVec2;
+use legion::*;
+use sfml::graphics::Color;
+
+use crate::components::*;
+use crate::forces::polygon_area;
+
+/// Systems spawn particles too, possibly in parallel
+pub fn id() -> usize {
+    static INDEX: AtomicUsize = AtomicUsize::new(0);
+
+    INDEX.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Properties shared by newly spawned particles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParticleTemplate {
+    pub radius: f64,
+    pub mass: f64,
+    pub charge: f64,
+    pub color: Color,
+    pub temperature: f64,
+    pub specific_heat: f64,
+}
+
+impl ParticleTemplate {
+    pub fn of_species(species: &SpeciesDefinition, temperature: f64, specific_heat: f64) -> Self {
+        Self {
+            radius: species.radius,
+            mass: species.mass,
+            charge: 0.0,
+            color: species.color,
+            temperature,
+            specific_heat,
+        }
+    }
+
+    /// Components of a new particle, ready to be pushed into a world or command buffer
+    pub fn components(
+        &self,
+        pos: DVec2,
+        vel: DVec2,
+    ) -> (
+        Id,
+        Mass,
+        Charge,
+        Position,
+        Velocity,
+        ShapeInfo,
+        FluidState,
+        Temperature,
+        HeatCapacity,
+    ) {
+        (
+            Id(id()),
+            Mass(self.mass),
+            Charge(self.charge),
+            Position(pos),
+            Velocity(vel),
+            ShapeInfo {
+                radius: self.radius,
+                color: self.color,
+            },
+            FluidState::default(),
+            Temperature(self.temperature),
+            HeatCapacity(self.specific_heat),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LinkKind {
+    Spring { stiffness: f64, damping: f64 },
+    Rigid,
+}
+
+/// Links two particles at their current distance
+pub fn link(world: &mut World, a: Entity, b: Entity, kind: LinkKind) {
+    let position = |e| {
+        world
+            .entry_ref(e)
+            .unwrap()
+            .get_component::<Position>()
+            .unwrap()
+            .0
+    };
+    let length = position(a).distance(position(b));
+
+    match kind {
+        LinkKind::Spring { stiffness, damping } => world.push((Spring {
+            a,
+            b,
+            rest_length: length,
+            stiffness,
+            damping,
+        },)),
+        LinkKind::Rigid => world.push((DistanceConstraint { a, b, length },)),
+    };
+}
+
+/// A chain of `segments` links from `start` to `end`, used for ropes and chains
+pub fn chain(
+    world: &mut World,
+    template: &ParticleTemplate,
+    start: DVec2,
+    end: DVec2,
+    segments: usize,
+    kind: LinkKind,
+    pin_start: bool,
+) -> Vec<Entity> {
+    let particles = (0..=segments)
+        .map(|i| {
+            let pos = start.lerp(end, i as f64 / segments as f64);
+            world.push(template.components(pos, DVec2::ZERO))
+        })
+        .collect::<Vec<_>>();
+
+    for pair in particles.windows(2) {
+        link(world, pair[0], pair[1], kind);
+    }
+
+    if pin_start {
+        world
+            .entry(particles[0])
+            .unwrap()
+            .add_component(Pinned(start));
+    }
+
+    particles
+}
+
+/// A `columns` x `rows` grid with structural and shear links,
+/// pinning the top row turns it into a hanging cloth
+pub fn grid(
+    world: &mut World,
+    template: &ParticleTemplate,
+    top_left: DVec2,
+    (columns, rows): (usize, usize),
+    spacing: f64,
+    kind: LinkKind,
+    pin_top: bool,
+) -> Vec<Entity> {
+    let particles = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (row, column)))
+        .map(|(row, column)| {
+            let pos = top_left + DVec2::new(column as f64, row as f64) * spacing;
+            world.push(template.components(pos, DVec2::ZERO))
+        })
+        .collect::<Vec<_>>();
+
+    let at = |row: usize, column: usize| particles[row * columns + column];
+
+    for row in 0..rows {
+        for column in 0..columns {
+            if column + 1 < columns {
+                link(world, at(row, column), at(row, column + 1), kind);
+            }
+
+            if row + 1 < rows {
+                link(world, at(row, column), at(row + 1, column), kind);
+            }
+
+            if row + 1 < rows && column + 1 < columns {
+                link(world, at(row, column), at(row + 1, column + 1), kind);
+                link(world, at(row, column + 1), at(row + 1, column), kind);
+            }
+        }
+    }
+
+    if pin_top {
+        for column in 0..columns {
+            let pos = top_left + DVec2::new(column as f64 * spacing, 0.0);
+            world
+                .entry(at(0, column))
+                .unwrap()
+                .add_component(Pinned(pos));
+        }
+    }
+
+    particles
+}
+
+/// A closed loop of `count` particles linked to their neighbours
+pub fn ring(
+    world: &mut World,
+    template: &ParticleTemplate,
+    centre: DVec2,
+    radius: f64,
+    count: usize,
+    kind: LinkKind,
+) -> Vec<Entity> {
+    let particles = (0..count)
+        .map(|i| {
+            let angle = std::f64::consts::TAU * i as f64 / count as f64;
+            let pos = centre + DVec2::from_angle(angle) * radius;
+            world.push(template.components(pos, DVec2::ZERO))
+        })
+        .collect::<Vec<_>>();
+
+    for i in 0..count {
+        link(world, particles[i], particles[(i + 1) % count], kind);
+    }
+
+    particles
+}
+
+/// Boundary of a `grid` in order, top row, right column, bottom row, left column
+pub fn grid_outline(particles: &[Entity], (columns, rows): (usize, usize)) -> Vec<Entity> {
+    let at = |row: usize, column: usize| particles[row * columns + column];
+
+    let top = (0..columns).map(|c| at(0, c));
+    let right = (1..rows).map(|r| at(r, columns - 1));
+    let bottom = (0..columns - 1).rev().map(|c| at(rows - 1, c));
+    let left = (1..rows - 1).rev().map(|r| at(r, 0));
+
+    top.chain(right).chain(bottom).chain(left).collect()
+}
+
+/// Turns already linked particles into a soft body, their
+/// current arrangement becomes the rest shape
+pub fn soft_body(
+    world: &mut World,
+    members: Vec<Entity>,
+    outline: Vec<Entity>,
+    pressure: f64,
+    shape_stiffness: f64,
+) -> Entity {
+    let position = |world: &World, e| {
+        world
+            .entry_ref(e)
+            .unwrap()
+            .get_component::<Position>()
+            .unwrap()
+            .0
+    };
+    let mass = |world: &World, e| {
+        world
+            .entry_ref(e)
+            .unwrap()
+            .get_component::<Mass>()
+            .unwrap()
+            .0
+    };
+
+    let total_mass = members.iter().map(|e| mass(world, *e)).sum::<f64>();
+    let centre = members
+        .iter()
+        .map(|e| position(world, *e) * mass(world, *e))
+        .sum::<DVec2>()
+        / total_mass;
+
+    let rest_shape = members
+        .iter()
+        .map(|e| position(world, *e) - centre)
+        .collect();
+    let rest_area = polygon_area(
+        &outline
+            .iter()
+            .map(|e| position(world, *e))
+            .collect::<Vec<_>>(),
+    )
+    .abs();
+
+    world.push((SoftBody {
+        members,
+        rest_shape,
+        outline,
+        rest_area,
+        pressure,
+        shape_stiffness,
+    },))
+}
+
+/// Spawns particles at `centre + offset` with the given radii and glues them into
+/// one rigid body, masses are scaled with the area relative to the template
+pub fn rigid_body(
+    world: &mut World,
+    template: &ParticleTemplate,
+    centre: DVec2,
+    parts: &[(DVec2, f64)],
+) -> Vec<Entity> {
+    let masses = parts
+        .iter()
+        .map(|(_, radius)| template.mass * (radius / template.radius).powi(2))
+        .collect::<Vec<_>>();
+
+    let mass = masses.iter().sum::<f64>();
+    let centre_of_mass = parts
+        .iter()
+        .zip(&masses)
+        .map(|((offset, _), m)| *offset * *m)
+        .sum::<DVec2>()
+        / mass;
+
+    let mut members = vec![];
+    let mut inertia = 0.0;
+
+    for ((offset, radius), m) in parts.iter().zip(&masses) {
+        let offset = *offset - centre_of_mass;
+        let template = ParticleTemplate {
+            radius: *radius,
+            mass: *m,
+            ..*template
+        };
+
+        members.push((
+            world.push(template.components(centre + offset, DVec2::ZERO)),
+            offset,
+        ));
+        // each particle is a solid disk
+        inertia += m * offset.length_squared() + 0.5 * m * radius * radius;
+    }
+
+    let particles = members.iter().map(|(e, _)| *e).collect::<Vec<_>>();
+
+    let body = world.push((RigidBody {
+        members,
+        position: centre,
+        angle: 0.0,
+        velocity: DVec2::ZERO,
+        angular_velocity: 0.0,
+        mass,
+        inertia,
+    },));
+
+    for member in &particles {
+        world
+            .entry(*member)
+            .unwrap()
+            .add_component(RigidMember(body));
+    }
+
+    particles
+}
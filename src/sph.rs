@@ -0,0 +1,37 @@
+//! 2D smoothing kernels for smoothed-particle hydrodynamics,
+//! `h` is the smoothing radius and every kernel is zero beyond it
+
+use std::f64::consts::PI;
+
+use glam::DVec2;
+
+/// Used for the density
+pub fn poly6(r: f64, h: f64) -> f64 {
+    if r >= h {
+        return 0.0;
+    }
+
+    4.0 / (PI * h.powi(8)) * (h * h - r * r).powi(3)
+}
+
+/// Gradient of the spiky kernel with respect to the first particle,
+/// `r` points from the second particle to the first one.
+/// Used for the pressure since it doesn't vanish close to the centre.
+pub fn spiky_gradient(r: DVec2, h: f64) -> DVec2 {
+    let distance = r.length();
+
+    if distance >= h || distance == 0.0 {
+        return DVec2::ZERO;
+    }
+
+    -30.0 / (PI * h.powi(5)) * (h - distance).powi(2) * r / distance
+}
+
+/// Laplacian of the viscosity kernel
+pub fn viscosity_laplacian(r: f64, h: f64) -> f64 {
+    if r >= h {
+        return 0.0;
+    }
+
+    40.0 / (PI * h.powi(5)) * (h - r)
+}
@@ -1,14 +1,102 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::time::Instant;
 
 use super::*;
 
+use glam::DVec2;
+
 use components::*;
 use quadtree::*;
 use collision::*;
+use flow::VectorField;
+use forces::*;
+use neighbours::*;
+use spawn::ParticleTemplate;
+
+use rand::Rng;
 
+use legion::systems::CommandBuffer;
 use legion::world::SubWorld;
 use legion::*;
 
+#[system]
+pub fn clear_collision_events(#[resource] events: &mut CollisionEvents) {
+    events.particles.clear();
+    events.walls.clear();
+}
+
+/// Finds the particles inside every sensor through the quadtree,
+/// which matches the positions at the start of the step
+#[system]
+pub fn update_sensors(
+    world: &mut SubWorld,
+    particles: &mut Query<(&Id, &Position)>,
+    sensors: &mut Query<&mut Sensor>,
+    #[resource] qt: &QuadTree<usize>,
+) {
+    let positions = index_by_id(
+        particles
+            .iter(world)
+            .map(|(Id(id), Position(pos))| (*id, *pos)),
+    );
+
+    for sensor in sensors.iter_mut(world) {
+        let (centre, radius) = sensor.region.bounding_circle();
+
+        let mut inside = qt
+            .query(centre, radius)
+            .into_iter()
+            .filter(|id| {
+                positions
+                    .get(id)
+                    .is_some_and(|pos| sensor.region.contains(*pos))
+            })
+            .collect::<Vec<_>>();
+        inside.sort_unstable();
+        inside.dedup();
+
+        sensor.entered = inside
+            .iter()
+            .filter(|id| sensor.inside.binary_search(id).is_err())
+            .copied()
+            .collect();
+        sensor.exited = sensor
+            .inside
+            .iter()
+            .filter(|id| inside.binary_search(id).is_err())
+            .copied()
+            .collect();
+
+        sensor.total_entered += sensor.entered.len();
+        sensor.total_exited += sensor.exited.len();
+        sensor.inside = inside;
+    }
+}
+
+#[system]
+pub fn drain_sinks(
+    world: &mut SubWorld,
+    particles: &mut Query<(Entity, &Position, &Mass)>,
+    sinks: &mut Query<&mut Sink>,
+    cmd: &mut CommandBuffer,
+) {
+    let (mut sink_world, particle_world) = world.split_for_query(sinks);
+    let mut sinks = sinks.iter_mut(&mut sink_world).collect::<Vec<_>>();
+
+    if sinks.is_empty() {
+        return;
+    }
+
+    for (entity, Position(pos), Mass(mass)) in particles.iter(&particle_world) {
+        if let Some(sink) = sinks.iter_mut().find(|sink| sink.region.contains(*pos)) {
+            sink.removed += 1;
+            sink.removed_mass += mass;
+            cmd.remove(*entity);
+        }
+    }
+}
+
 #[system(for_each)]
 pub fn update_positions(pos: &mut Position, vel: &Velocity, #[resource] dt: &f32) {
     let dt = *dt as f64;
@@ -17,147 +105,1456 @@ pub fn update_positions(pos: &mut Position, vel: &Velocity, #[resource] dt: &f32
     pos.0.y += vel.0.y * dt;
 }
 
+/// The tracker is kinematic, particles bounce off it relative to its velocity
 #[system]
 pub fn handle_mouse_collision(
     world: &mut SubWorld,
-    query: &mut Query<(&mut Position, &mut Velocity, &ShapeInfo)>,
-    #[resource] MouseTracker { radius, pos }: &MouseTracker,
+    mouse: &mut Query<(&MouseTracker, Option<&Disabled>)>,
+    query: &mut Query<(
+        &mut Position,
+        &mut Velocity,
+        &ShapeInfo,
+        Option<&CollisionGroup>,
+    )>,
+    #[resource] settings: &MouseSettings,
 ) {
-    query.for_each_mut(world, |(pos1, vel, shape)| {
-        let distance = (pos - pos1.0).length();
-        let combined_radius = radius + shape.radius;
+    if settings.mode != MouseMode::Push {
+        return;
+    }
 
-        if distance < combined_radius {
-            let overlap = combined_radius - distance;
-            let normal = (pos - pos1.0).normalize();
-            let correction = normal * overlap / 2.0;
+    let Some(tracker) = mouse
+        .iter(world)
+        .find(|(_, disabled)| disabled.is_none())
+        .map(|(tracker, _)| *tracker)
+    else {
+        return;
+    };
 
-            *vel = Velocity(vel.0 - 2.0 * vel.0.dot(normal) * normal);
-            pos1.0 -= correction
+    let (start, end) = tracker.segment();
+
+    query.for_each_mut(world, |(Position(pos), Velocity(vel), shape, group)| {
+        if !group
+            .copied()
+            .unwrap_or_default()
+            .hits(CollisionGroup::MOUSE)
+        {
+            return;
+        }
+
+        let closest = closest_point_on_segment(*pos, start, end);
+        let distance = pos.distance(closest);
+        let combined_radius = tracker.radius + shape.radius;
+
+        if distance >= combined_radius {
+            return;
+        }
+
+        let normal = (*pos - closest)
+            .try_normalize()
+            .unwrap_or(tracker.velocity.normalize_or(DVec2::NEG_Y));
+        *pos = closest + normal * combined_radius;
+
+        let approach_speed = (*vel - tracker.velocity).dot(normal);
+
+        if approach_speed < 0.0 {
+            *vel -= (1.0 + settings.restitution) * approach_speed * normal;
         }
     });
 }
 
+/// Rebuilds the neighbour lists once a particle has moved more than half the skin
 #[system]
+#[allow(clippy::too_many_arguments)]
+pub fn update_neighbour_list(
+    world: &mut SubWorld,
+    query: &mut Query<(&Id, &Position, &ShapeInfo)>,
+    #[resource] list: &mut NeighbourList,
+    #[resource] mode: &InteractionMode,
+    #[resource] pair: &PairPotentialSettings,
+    #[resource] electrostatics: &ElectrostaticsSettings,
+    #[resource] sph: &SphSettings,
+    #[resource] size: &sfml::system::Vector2u,
+) {
+    if !list.enabled {
+        return;
+    }
+
+    let mut max_radius: f64 = 0.0;
+    let positions = query
+        .iter(world)
+        .map(|(Id(id), Position(pos), ShapeInfo { radius, .. })| {
+            max_radius = max_radius.max(*radius);
+            (*id, *pos)
+        })
+        .collect::<Vec<_>>();
+
+    let mut range = 2.0 * max_radius;
+
+    match mode {
+        InteractionMode::PairPotential => range = range.max(pair.cutoff),
+        InteractionMode::Sph => range = range.max(sph.smoothing_radius),
+        InteractionMode::HardSpheres | InteractionMode::PositionBasedFluid => {}
+    }
+
+    if electrostatics.enabled {
+        range = range.max(electrostatics.cutoff);
+    }
+
+    if list.needs_rebuild(&positions, range) {
+        list.rebuild(&positions, range, sfml::graphics::Rect {
+            left: 0.,
+            top: 0.,
+            width: size.x as _,
+            height: size.y as _,
+        });
+    }
+}
+
+/// Copy of a particle's components while its collisions are resolved
+#[derive(Clone, Copy)]
+struct CollisionBody {
+    entity: Entity,
+    id: usize,
+    mass: Mass,
+    pos: Position,
+    vel: Velocity,
+    shape: ShapeInfo,
+    charge: f64,
+    /// `None` for particles without a [`Temperature`], they don't exchange heat
+    temperature: Option<f64>,
+    /// 1 for particles without a [`HeatCapacity`]
+    specific_heat: f64,
+    has_heat_capacity: bool,
+    group: CollisionGroup,
+    /// Passed on to fragments
+    lifetime: Option<Lifetime>,
+    species: Option<Species>,
+    drag: Option<Drag>,
+    /// Members of the same rigid body don't collide
+    rigid_body: Option<Entity>,
+    updated: bool,
+}
+
+#[system]
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn handle_collisions(
     world: &mut SubWorld,
-    query: &mut Query<(&Id, &Mass, &mut Position, &mut Velocity, &ShapeInfo)>,
+    query: &mut Query<(
+        Entity,
+        &Id,
+        &mut Mass,
+        &mut Position,
+        &mut Velocity,
+        &mut ShapeInfo,
+        Option<&Charge>,
+        Option<&mut Temperature>,
+        Option<&mut HeatCapacity>,
+        Option<&CollisionGroup>,
+        Option<&Lifetime>,
+        Option<&Species>,
+        Option<&Drag>,
+        Option<&RigidMember>,
+    )>,
+    cmd: &mut CommandBuffer,
     #[resource] qt: &QuadTree<usize>,
+    #[resource] neighbours: &NeighbourList,
     #[resource] slower_detection: &bool,
+    #[resource] mode: &InteractionMode,
+    #[resource] response: &CollisionResponse,
+    #[resource] fragmentation: &FragmentationSettings,
+    #[resource] heat: &HeatSettings,
+    #[resource] events: &mut CollisionEvents,
     #[resource] time: &mut CollisionDetectionTime,
+    #[resource] dt: &f32,
 ) {
+    if *mode != InteractionMode::HardSpheres {
+        time.0 = 0;
+        return;
+    }
+
     let clock = Instant::now();
-    if !*slower_detection {
-        let mut entities = [const { None }; 20_000];
 
-        query.for_each_mut(world, |(Id(id), a, b, c, d)| {
-            entities[*id] = Some((*a, *b, *c, *d, false)); // false means no update
-        });
+    // removed particles become `None`
+    let mut entities = query
+        .iter_mut(world)
+        .map(
+            |(
+                entity,
+                Id(id),
+                mass,
+                pos,
+                vel,
+                shape,
+                charge,
+                temperature,
+                specific_heat,
+                group,
+                lifetime,
+                species,
+                drag,
+                member,
+            )| {
+                Some(CollisionBody {
+                    entity: *entity,
+                    id: *id,
+                    mass: *mass,
+                    pos: *pos,
+                    vel: *vel,
+                    shape: *shape,
+                    charge: charge.map_or(0.0, |c| c.0),
+                    temperature: temperature.map(|t| t.0),
+                    specific_heat: specific_heat.as_ref().map_or(1.0, |c| c.0),
+                    has_heat_capacity: specific_heat.is_some(),
+                    group: group.copied().unwrap_or_default(),
+                    lifetime: lifetime.copied(),
+                    species: species.copied(),
+                    drag: drag.copied(),
+                    rigid_body: member.map(|m| m.0),
+                    updated: false,
+                })
+            },
+        )
+        .collect::<Vec<_>>();
+
+    let index = index_by_id(
+        entities
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(|(i, body)| (body.id, i)),
+    );
+    let mut particle_count = entities.len();
+
+    for i in 0..entities.len() {
+        let Some(CollisionBody {
+            id: id1,
+            mass: Mass(m1),
+            pos: Position(pos1),
+            vel: vel1,
+            shape: ShapeInfo { radius, .. },
+            group: group1,
+            rigid_body: body1,
+            updated: updated1,
+            ..
+        }) = entities[i]
+        else {
+            continue;
+        };
+
+        if updated1 && !*slower_detection {
+            continue;
+        }
+
+        // the slower detection checks every pair once, also those which already collided
+        let colliding = if *slower_detection {
+            ((i + 1)..entities.len()).collect::<Vec<_>>()
+        } else {
+            neighbours_of(qt, neighbours, id1, pos1, radius)
+                .iter()
+                .filter_map(|id| index.get(id).copied())
+                .collect()
+        };
 
-        for i in 0..entities.len() {
-            let Some((Mass(m1), Position(pos1), vel1, ShapeInfo { radius, .. }, _)) =
-                entities[i].as_ref()
+        for colliding_obj_id in colliding {
+            let Some(CollisionBody {
+                mass: Mass(m2),
+                pos: Position(pos2),
+                vel: vel2,
+                shape,
+                group: group2,
+                rigid_body: body2,
+                updated: updated2,
+                ..
+            }) = entities[colliding_obj_id]
             else {
-                break;
+                continue;
             };
 
-            let pos1 = *pos1;
-            let m1 = *m1;
-            let vel1 = *vel1;
-            let radius = *radius;
+            if i == colliding_obj_id
+                || (updated2 && !*slower_detection)
+                || !group1.collides_with(&group2)
+                || (body1.is_some() && body1 == body2)
+            {
+                continue;
+            }
 
-            let colliding = qt.query(pos1, radius);
+            let distance = (pos1 - pos2).length();
+            let combined_radius = radius + shape.radius;
 
-            if entities[i].as_ref().unwrap().4 {
+            // coincident particles have no collision normal
+            if distance > combined_radius || distance == 0.0 {
                 continue;
             }
 
-            for colliding_obj_id in colliding {
-                if i == colliding_obj_id || entities[colliding_obj_id].as_ref().unwrap().4 {
-                    continue;
+            let overlap = combined_radius - distance;
+            let direction = (pos1 - pos2).normalize_or_zero();
+            let point = pos2 + direction * (shape.radius - overlap / 2.0);
+
+            if *response == CollisionResponse::Merge {
+                let a = entities[i].take().unwrap();
+                let b = entities[colliding_obj_id].take().unwrap();
+                let (mass, pos, vel, shape) = merge(
+                    (a.mass, a.pos, a.vel, a.shape),
+                    (b.mass, b.pos, b.vel, b.shape),
+                );
+
+                events.particles.push(CollisionEvent {
+                    a: a.entity,
+                    b: b.entity,
+                    point,
+                    normal: direction,
+                    impulse: a.mass.0 * (vel.0 - a.vel.0).length(),
+                });
+
+                // the heavier particle survives
+                let (survivor, body, removed) = if a.mass.0 >= b.mass.0 {
+                    (i, a, b)
+                } else {
+                    (colliding_obj_id, b, a)
+                };
+
+                // the merged particle holds the heat of both
+                let (c1, c2) = (a.mass.0 * a.specific_heat, b.mass.0 * b.specific_heat);
+                let temperature = match (a.temperature, b.temperature) {
+                    (Some(t1), Some(t2)) => Some((t1 * c1 + t2 * c2) / (c1 + c2))
+                        .filter(|t| t.is_finite())
+                        .or(body.temperature),
+                    _ => body.temperature,
+                };
+
+                entities[survivor] = Some(CollisionBody {
+                    mass,
+                    pos,
+                    vel,
+                    shape,
+                    temperature,
+                    specific_heat: (c1 + c2) / mass.0,
+                    updated: true,
+                    ..body
+                });
+
+                cmd.remove(removed.entity);
+                break;
+            }
+
+            let (new_vel1, new_vel2) = process_collision(vel1.0, vel2.0, pos1, pos2, m1, m2);
+            let correction = direction * overlap / 2.0;
+
+            events.particles.push(CollisionEvent {
+                a: entities[i].unwrap().entity,
+                b: entities[colliding_obj_id].unwrap().entity,
+                point,
+                normal: direction,
+                impulse: m1 * (new_vel1 - vel1.0).length(),
+            });
+
+            let a = entities[i].unwrap();
+            let b = entities[colliding_obj_id].unwrap();
+
+            let heat = match (heat.enabled, a.temperature, b.temperature) {
+                (true, Some(t1), Some(t2)) => heat_flow(
+                    t1,
+                    a.mass.0 * a.specific_heat,
+                    t2,
+                    b.mass.0 * b.specific_heat,
+                    heat.conductance * radius.min(shape.radius),
+                    *dt as f64,
+                ),
+                _ => 0.0,
+            };
+
+            let body = entities[i].as_mut().unwrap();
+            body.updated = true;
+            body.pos.0 += correction;
+            body.vel.0 = new_vel1;
+            if let Some(temperature) = &mut body.temperature {
+                *temperature += heat / (body.mass.0 * body.specific_heat);
+            }
+
+            let body = entities[colliding_obj_id].as_mut().unwrap();
+            body.updated = true;
+            body.pos.0 -= correction;
+            body.vel.0 = new_vel2;
+            if let Some(temperature) = &mut body.temperature {
+                *temperature -= heat / (body.mass.0 * body.specific_heat);
+            }
+
+            // particles already moving apart don't shatter
+            let impact_speed = (-(vel1.0 - vel2.0).dot(direction)).max(0.0);
+
+            if fragmentation.enabled && impact_speed > fragmentation.threshold {
+                let count = fragmentation.fragments;
+
+                for id in [i, colliding_obj_id] {
+                    let body = entities[id].unwrap();
+                    let radius = body.shape.radius / (count as f64).sqrt();
+
+                    if radius < fragmentation.min_radius
+                        || particle_count + count - 1 > fragmentation.max_particles
+                    {
+                        continue;
+                    }
+
+                    let template = ParticleTemplate {
+                        radius,
+                        mass: body.mass.0 / count as f64,
+                        charge: body.charge / count as f64,
+                        color: body.shape.color,
+                        temperature: body.temperature.unwrap_or_default(),
+                        specific_heat: body.specific_heat,
+                    };
+
+                    // spaced so they don't immediately collide and shatter again
+                    for (pos, vel) in fragment(
+                        body.pos.0,
+                        body.vel.0,
+                        (body.shape.radius - radius).max(fragment_spacing(radius, count)),
+                        count,
+                        fragmentation.spread * impact_speed,
+                    ) {
+                        let fragment = cmd.push(template.components(pos, vel));
+
+                        // the template always has them, fragments only keep what the parent had
+                        if body.temperature.is_none() {
+                            cmd.remove_component::<Temperature>(fragment);
+                        }
+
+                        if !body.has_heat_capacity {
+                            cmd.remove_component::<HeatCapacity>(fragment);
+                        }
+
+                        if body.group != CollisionGroup::default() {
+                            cmd.add_component(fragment, body.group);
+                        }
+
+                        if let Some(lifetime) = body.lifetime {
+                            cmd.add_component(fragment, lifetime);
+                        }
+
+                        if let Some(species) = body.species {
+                            cmd.add_component(fragment, species);
+                        }
+
+                        if let Some(drag) = body.drag {
+                            cmd.add_component(fragment, drag);
+                        }
+                    }
+
+                    cmd.remove(body.entity);
+                    entities[id] = None;
+                    particle_count += count - 1;
                 }
 
-                let (m2, Position(pos2), vel2, shape, _) =
-                    &entities[colliding_obj_id].as_ref().unwrap();
+                if entities[i].is_none() {
+                    break;
+                }
+            }
+        }
+    }
 
-                let distance = (pos1 - pos2).length();
-                let combined_radius = radius + shape.radius;
+    query.iter_mut(world).for_each(
+        |(_, id, mass, pos, vel, shape, _, temperature, specific_heat, ..)| {
+            if let Some(body) = entities[index[&id.0]]
+                && body.updated
+            {
+                *mass = body.mass;
+                *pos = body.pos;
+                *vel = body.vel;
+                *shape = body.shape;
 
-                if distance > combined_radius {
-                    continue;
+                if let (Some(temperature), Some(new)) = (temperature, body.temperature) {
+                    temperature.0 = new;
                 }
 
-                let (new_vel1, new_vel2) = process_collision(vel1.0, vel2.0, pos1, *pos2, m1, m2.0);
+                if let Some(specific_heat) = specific_heat {
+                    specific_heat.0 = body.specific_heat;
+                }
+            }
+        },
+    );
 
-                let overlap = combined_radius - distance;
-                let direction = (pos1 - pos2).normalize();
-                let correction = direction * overlap / 2.0;
+    time.0 = clock.elapsed().as_nanos();
+}
 
-                let (_, pos, vel, _, update) = entities[i].as_mut().unwrap();
-                *update = true;
-                pos.0 += correction;
-                vel.0 = new_vel1;
+/// Copy of a particle's components while its reactions are resolved
+#[derive(Clone, Copy)]
+struct Reactant {
+    entity: Entity,
+    species: usize,
+    mass: f64,
+    pos: DVec2,
+    vel: DVec2,
+    radius: f64,
+    heat_capacity: f64,
+    temperature: f64,
+    group: CollisionGroup,
+    rigid_body: Option<Entity>,
+}
 
-                let (_, pos, vel, _, update) = entities[colliding_obj_id].as_mut().unwrap();
-                *update = true;
-                pos.0 -= correction;
-                vel.0 = new_vel2;
+/// Touching particles react according to the first matching [`ReactionRule`],
+/// every particle takes part in at most one reaction per step
+#[system]
+#[allow(clippy::type_complexity)]
+pub fn apply_reactions(
+    world: &mut SubWorld,
+    query: &mut Query<(
+        Entity,
+        &Id,
+        &mut Species,
+        &mut Mass,
+        &Position,
+        &mut Velocity,
+        &mut ShapeInfo,
+        &Temperature,
+        &HeatCapacity,
+        Option<&CollisionGroup>,
+        Option<&RigidMember>,
+    )>,
+    cmd: &mut CommandBuffer,
+    #[resource] qt: &QuadTree<usize>,
+    #[resource] neighbours: &NeighbourList,
+    #[resource] chemistry: &Chemistry,
+) {
+    if !chemistry.enabled || chemistry.reactions.is_empty() {
+        return;
+    }
+
+    let mut particles = index_by_id(query.iter_mut(world).map(
+        |(
+            entity,
+            Id(id),
+            Species(species),
+            Mass(mass),
+            Position(pos),
+            Velocity(vel),
+            shape,
+            t,
+            c,
+            group,
+            member,
+        )| {
+            let reactant = Reactant {
+                entity: *entity,
+                species: *species,
+                mass: *mass,
+                pos: *pos,
+                vel: *vel,
+                radius: shape.radius,
+                heat_capacity: *mass * c.0,
+                temperature: t.0,
+                group: group.copied().unwrap_or_default(),
+                rigid_body: member.map(|m| m.0),
+            };
+
+            (*id, reactant)
+        },
+    ));
+
+    // species of the particles which turned into another one in place
+    let mut replaced = HashMap::new();
+    let mut rng = rand::thread_rng();
+
+    let mut ids = particles.keys().copied().collect::<Vec<_>>();
+    ids.sort_unstable();
+
+    for i in ids {
+        let Some(a) = particles.get(&i).copied() else {
+            continue;
+        };
+
+        for &j in neighbours_of(qt, neighbours, i, a.pos, a.radius).iter() {
+            let Some(b) = particles.get(&j).copied() else {
+                continue;
+            };
+
+            let offset = b.pos - a.pos;
+            let distance = offset.length();
+
+            if i == j
+                || distance > a.radius + b.radius
+                || distance == 0.0
+                || !a.group.collides_with(&b.group)
+                || (a.rigid_body.is_some() && a.rigid_body == b.rigid_body)
+            {
+                continue;
+            }
+
+            let Some((rule, swapped)) = chemistry.reaction(a.species, b.species) else {
+                continue;
+            };
+
+            let approach_speed = (a.vel - b.vel).dot(offset / distance);
+            let reduced_mass = a.mass * b.mass / (a.mass + b.mass);
+
+            if approach_speed <= 0.0
+                || 0.5 * reduced_mass * approach_speed.powi(2) < rule.activation_energy
+                || !rng.gen_bool(rule.probability.clamp(0.0, 1.0))
+            {
+                continue;
+            }
+
+            particles.remove(&i);
+            particles.remove(&j);
+
+            if let [first, second] = rule.products[..] {
+                let (first_id, second_id) = if swapped { (j, i) } else { (i, j) };
+                replaced.insert(first_id, first);
+                replaced.insert(second_id, second);
+                break;
             }
+
+            cmd.remove(a.entity);
+            cmd.remove(b.entity);
+
+            let mass = a.mass + b.mass;
+            let centre = (a.pos * a.mass + b.pos * b.mass) / mass;
+            let vel = (a.vel * a.mass + b.vel * b.mass) / mass;
+            let heat_capacity = a.heat_capacity + b.heat_capacity;
+            let temperature =
+                (a.temperature * a.heat_capacity + b.temperature * b.heat_capacity) / heat_capacity;
+            let count = rule.products.len();
+            // the products share the momentum of the reactants whatever their masses
+            let product_mass = rule
+                .products
+                .iter()
+                .map(|product| chemistry.species[*product].mass)
+                .sum::<f64>();
+            let vel = vel * mass / product_mass;
+            let distance = if count > 1 {
+                a.radius.max(b.radius)
+            } else {
+                0.0
+            };
+
+            for (&product, (pos, vel)) in rule
+                .products
+                .iter()
+                .zip(fragment(centre, vel, distance, count, 0.0))
+            {
+                let template = ParticleTemplate::of_species(
+                    &chemistry.species[product],
+                    temperature,
+                    heat_capacity / mass,
+                );
+
+                let entity = cmd.push(template.components(pos, vel));
+                cmd.add_component(entity, Species(product));
+            }
+
+            break;
         }
+    }
 
-        query.iter_mut(world).for_each(|(id, _, pos, vel, _)| {
-            let (_, new_pos, new_vel, _, update) = entities[id.0].as_ref().unwrap();
+    query
+        .iter_mut(world)
+        .for_each(|(_, Id(id), species, mass, _, vel, shape, ..)| {
+            if let Some(&product) = replaced.get(id) {
+                let definition = &chemistry.species[product];
 
-            if *update {
-                *pos = *new_pos;
-                *vel = *new_vel;
+                // each product keeps the momentum of the particle it replaces
+                vel.0 *= mass.0 / definition.mass;
+                species.0 = product;
+                mass.0 = definition.mass;
+                shape.radius = definition.radius;
+                shape.color = definition.color;
             }
         });
+}
+
+#[system]
+pub fn record_species_history(
+    world: &SubWorld,
+    query: &mut Query<&Species>,
+    #[resource] chemistry: &Chemistry,
+    #[resource] history: &mut SpeciesHistory,
+    #[resource] dt: &f32,
+) {
+    history.elapsed += *dt as f64;
+
+    if history.elapsed < history.interval {
+        return;
+    }
+
+    history.elapsed = 0.0;
+
+    let mut counts = vec![0; chemistry.species.len()];
+    for Species(species) in query.iter(world) {
+        if let Some(count) = counts.get_mut(*species) {
+            *count += 1;
+        }
+    }
+
+    history.samples.push_back(counts);
+    while history.samples.len() > history.capacity {
+        history.samples.pop_front();
+    }
+}
+
+/// Force field entities, and the mouse while it acts as an attractor
+#[system]
+pub fn apply_force_fields(
+    world: &mut SubWorld,
+    fields: &mut Query<&ForceField>,
+    mouse: &mut Query<(&MouseTracker, Option<&Disabled>)>,
+    particles: &mut Query<(&Position, &mut Velocity)>,
+    #[resource] settings: &MouseSettings,
+    #[resource] dt: &f32,
+) {
+    let mut active = fields.iter(world).copied().collect::<Vec<_>>();
+
+    if settings.mode == MouseMode::Attract {
+        active.extend(
+            mouse
+                .iter(world)
+                .filter(|(_, disabled)| disabled.is_none())
+                .map(|(tracker, _)| ForceField::Radial {
+                    centre: tracker.pos,
+                    radius: settings.radius,
+                    strength: settings.strength,
+                    falloff: settings.falloff,
+                }),
+        );
+    }
+
+    if active.is_empty() {
+        return;
+    }
+
+    let dt = *dt as f64;
+
+    particles.for_each_mut(world, |(Position(pos), Velocity(vel))| {
+        *vel += active
+            .iter()
+            .map(|field| field.acceleration(*pos))
+            .sum::<DVec2>()
+            * dt;
+    });
+}
+
+#[system]
+pub fn apply_gravitational_attraction(
+    world: &mut SubWorld,
+    query: &mut Query<(&Id, &Mass, &Position, &mut Velocity)>,
+    #[resource] qt: &mut QuadTree<usize>,
+    #[resource] settings: &NBodySettings,
+    #[resource] stats: &mut NBodyStats,
+    #[resource] dt: &f32,
+) {
+    if !settings.enabled {
+        return;
+    }
+
+    let clock = Instant::now();
+    let dt = *dt as f64;
+
+    let bodies = query
+        .iter_mut(world)
+        .map(|(Id(id), Mass(mass), Position(pos), _)| (*id, *pos, *mass))
+        .collect::<Vec<_>>();
+
+    let exact = || {
+        direct_gravity(
+            &bodies
+                .iter()
+                .map(|(_, pos, mass)| (*pos, *mass))
+                .collect::<Vec<_>>(),
+            settings.g,
+            settings.softening,
+        )
+    };
+
+    let accelerations = if settings.exact {
+        stats.mean_relative_error = None;
+        exact()
     } else {
-        let entities = query.iter_mut(world).collect::<Vec<_>>();
-        let mut updated = [None; 20_000];
+        let masses = index_by_id(bodies.iter().map(|(id, _, mass)| (*id, *mass)));
+        let mass_of = |id: &usize| masses.get(id).copied().unwrap_or(0.0);
 
-        // Check collisions for all pairs
-        for i in 0..entities.len() {
-            for j in (i + 1)..entities.len() {
-                let (id1, mass1, pos1, vel1, shape1) = &entities[i];
-                let (id2, mass2, pos2, vel2, shape2) = &entities[j];
+        qt.compute_mass_distribution(&mass_of);
 
-                // Calculate distance and combined radius
-                let distance = (pos1.0 - pos2.0).length();
-                let combined_radius = shape1.radius + shape2.radius;
+        let approximated = bodies
+            .iter()
+            .map(|(id, pos, _)| {
+                qt.gravitational_acceleration(
+                    *pos,
+                    id,
+                    settings.theta,
+                    settings.g,
+                    settings.softening,
+                    &mass_of,
+                )
+            })
+            .collect::<Vec<_>>();
 
-                if distance < combined_radius {
-                    // Collision detected, process velocities
-                    let (new_vel1, new_vel2) =
-                        process_collision(vel1.0, vel2.0, pos1.0, pos2.0, mass1.0, mass2.0);
+        stats.mean_relative_error = (settings.measure_error && !bodies.is_empty()).then(|| {
+            approximated
+                .iter()
+                .zip(exact())
+                .map(|(a, b)| (*a - b).length() / b.length().max(f64::EPSILON))
+                .sum::<f64>()
+                / bodies.len() as f64
+        });
 
-                    let overlap = combined_radius - distance;
-                    let direction = (pos1.0 - pos2.0).normalize();
-                    let correction = direction * overlap / 2.0;
+        approximated
+    };
 
-                    let mut pos1 = **pos1;
-                    let mut pos2 = **pos2;
+    query
+        .iter_mut(world)
+        .zip(accelerations)
+        .for_each(|((_, _, _, vel), acceleration)| vel.0 += acceleration * dt);
 
-                    // Move the particles apart
-                    pos1.0 += correction;
-                    pos2.0 -= correction;
+    stats.time = clock.elapsed().as_nanos();
+}
 
-                    updated[id1.0] = Some((new_vel1, pos1));
-                    updated[id2.0] = Some((new_vel2, pos2));
-                }
+#[system]
+pub fn apply_coulomb_forces(
+    world: &mut SubWorld,
+    query: &mut Query<(&Id, &Mass, &Charge, &Position, &mut Velocity)>,
+    #[resource] qt: &QuadTree<usize>,
+    #[resource] neighbours: &NeighbourList,
+    #[resource] settings: &ElectrostaticsSettings,
+    #[resource] dt: &f32,
+) {
+    if !settings.enabled {
+        return;
+    }
+
+    let dt = *dt as f64;
+
+    let charges = index_by_id(
+        query
+            .iter_mut(world)
+            .map(|(Id(id), _, Charge(q), Position(pos), _)| (*id, (*pos, *q))),
+    );
+
+    query.for_each_mut(
+        world,
+        |(Id(id), Mass(m), Charge(q1), Position(pos1), vel)| {
+            if *q1 == 0.0 {
+                return;
+            }
+
+            let force = neighbours_of(qt, neighbours, *id, *pos1, settings.cutoff)
+                .iter()
+                .filter(|other| *other != id)
+                .filter_map(|other| charges.get(other).copied())
+                .filter(|(pos2, q2)| *q2 != 0.0 && pos1.distance(*pos2) <= settings.cutoff)
+                .map(|(pos2, q2)| {
+                    coulomb_force(*pos1, pos2, *q1, q2, settings.k, settings.softening)
+                })
+                .sum::<DVec2>();
+
+            vel.0 += force / m * dt;
+        },
+    );
+}
+
+#[system]
+#[allow(clippy::too_many_arguments)]
+pub fn apply_pair_potential(
+    world: &mut SubWorld,
+    query: &mut Query<(&Id, &Mass, &Position, &mut Velocity)>,
+    #[resource] qt: &QuadTree<usize>,
+    #[resource] neighbours: &NeighbourList,
+    #[resource] mode: &InteractionMode,
+    #[resource] settings: &PairPotentialSettings,
+    #[resource] energy: &mut EnergyStats,
+    #[resource] dt: &f32,
+) {
+    energy.potential = 0.0;
+
+    if *mode != InteractionMode::PairPotential {
+        return;
+    }
+
+    let dt = *dt as f64;
+    let shift = if settings.shift {
+        settings.potential.evaluate(settings.cutoff).0
+    } else {
+        0.0
+    };
+
+    let positions = index_by_id(
+        query
+            .iter_mut(world)
+            .map(|(Id(id), _, Position(pos), _)| (*id, *pos)),
+    );
+
+    query.for_each_mut(world, |(Id(id), Mass(m), Position(pos1), vel)| {
+        let mut force = DVec2::ZERO;
+
+        for &other in neighbours_of(qt, neighbours, *id, *pos1, settings.cutoff).iter() {
+            let Some(pos2) = positions.get(&other).copied() else {
+                continue;
+            };
+
+            let distance = pos1.distance(pos2);
+            if other == *id || distance > settings.cutoff || distance == 0.0 {
+                continue;
             }
+
+            let (e, f) = settings.potential.evaluate(distance);
+
+            force += f * (*pos1 - pos2) / distance;
+            // every pair is visited twice
+            energy.potential += 0.5 * (e - shift);
         }
 
-        query.iter_mut(world).for_each(|(id, _, pos, vel, _)| {
-            if let Some((new_vel, new_pos)) = updated[id.0] {
-                *vel = Velocity(new_vel);
-                *pos = new_pos;
+        vel.0 += force / m * dt;
+    });
+}
+
+#[system]
+#[allow(clippy::too_many_arguments)]
+pub fn apply_sph(
+    world: &mut SubWorld,
+    query: &mut Query<(&Id, &Mass, &Position, &mut Velocity, &mut FluidState)>,
+    #[resource] qt: &QuadTree<usize>,
+    #[resource] neighbours: &NeighbourList,
+    #[resource] mode: &InteractionMode,
+    #[resource] settings: &SphSettings,
+    #[resource] dt: &f32,
+) {
+    if *mode != InteractionMode::Sph {
+        return;
+    }
+
+    let dt = *dt as f64;
+    let h = settings.smoothing_radius;
+
+    let particles = index_by_id(
+        query
+            .iter_mut(world)
+            .map(|(Id(id), Mass(m), Position(pos), Velocity(vel), _)| (*id, (*pos, *vel, *m))),
+    );
+
+    let neighbourhood = |id: usize, pos: DVec2| {
+        neighbours_of(qt, neighbours, id, pos, h)
+            .iter()
+            .filter(|other| **other != id)
+            .filter_map(|other| particles.get(other).copied())
+            .filter(|(pos2, ..)| pos.distance(*pos2) < h)
+            .collect::<Vec<_>>()
+    };
+
+    // density and pressure first, the forces need them for every neighbour
+    query.for_each_mut(world, |(Id(id), Mass(m), Position(pos), _, fluid)| {
+        fluid.density = m * sph::poly6(0.0, h)
+            + neighbourhood(*id, *pos)
+                .iter()
+                .map(|(pos2, _, m2)| m2 * sph::poly6(pos.distance(*pos2), h))
+                .sum::<f64>();
+
+        // negative pressure makes particles clump together
+        fluid.pressure = (settings.stiffness * (fluid.density - settings.rest_density)).max(0.0);
+    });
+
+    let fluid = index_by_id(
+        query
+            .iter_mut(world)
+            .map(|(Id(id), _, _, _, fluid)| (*id, *fluid)),
+    );
+
+    query.for_each_mut(world, |(Id(id), _, Position(pos), vel, state)| {
+        let mut acceleration = DVec2::ZERO;
+
+        for other in neighbours_of(qt, neighbours, *id, *pos, h).iter() {
+            let (Some((pos2, vel2, m2)), Some(other_state)) =
+                (particles.get(other).copied(), fluid.get(other).copied())
+            else {
+                continue;
+            };
+
+            let r = *pos - pos2;
+            let distance = r.length();
+
+            if *other == *id || distance >= h || other_state.density == 0.0 {
+                continue;
             }
+
+            let pressure = -m2 * (state.pressure + other_state.pressure)
+                / (2.0 * other_state.density)
+                * sph::spiky_gradient(r, h);
+
+            let viscosity = settings.viscosity * m2 * (vel2 - vel.0) / other_state.density
+                * sph::viscosity_laplacian(distance, h);
+
+            acceleration += (pressure + viscosity) / state.density;
+
+            // cohesion pulling neighbours together
+            acceleration -= settings.surface_tension * m2 * r * sph::poly6(distance, h);
+        }
+
+        vel.0 += acceleration * dt;
+    });
+}
+
+/// Runs after `update_positions`, treating the integrated positions as predictions
+/// and projecting them onto the density constraints
+#[system]
+pub fn solve_position_based_fluid(
+    world: &mut SubWorld,
+    query: &mut Query<(
+        &Mass,
+        &ShapeInfo,
+        &mut Position,
+        &mut Velocity,
+        &mut FluidState,
+    )>,
+    #[resource] mode: &InteractionMode,
+    #[resource] settings: &PbfSettings,
+    #[resource] size: &sfml::system::Vector2u,
+    #[resource] dt: &f32,
+) {
+    if *mode != InteractionMode::PositionBasedFluid || *dt == 0.0 {
+        return;
+    }
+
+    let dt = *dt as f64;
+    let h = settings.smoothing_radius;
+    let rho0 = settings.rest_density;
+
+    let particles = query
+        .iter_mut(world)
+        .map(|(Mass(m), shape, Position(pos), Velocity(vel), _)| (*m, shape.radius, *pos, *vel))
+        .collect::<Vec<_>>();
+
+    let mass = particles.iter().map(|(m, ..)| *m).collect::<Vec<_>>();
+    let mut predicted = particles
+        .iter()
+        .map(|(_, _, pos, _)| *pos)
+        .collect::<Vec<_>>();
+    let previous = particles
+        .iter()
+        .map(|(_, _, pos, vel)| *pos - *vel * dt)
+        .collect::<Vec<_>>();
+
+    // neighbours are only searched once per frame
+    let mut qt = QuadTree::new(8, sfml::graphics::Rect {
+        left: 0.,
+        top: 0.,
+        width: size.x as _,
+        height: size.y as _,
+    });
+
+    for (i, pos) in predicted.iter().enumerate() {
+        qt.push((*pos, 0.0, i));
+    }
+
+    let neighbours = predicted
+        .iter()
+        .enumerate()
+        .map(|(i, pos)| {
+            let mut found = qt.query(*pos, h);
+            found.retain(|j| *j != i);
+            found
+        })
+        .collect::<Vec<_>>();
+
+    let w_dq = sph::poly6(0.2 * h, h);
+    let mut density = vec![0.0; predicted.len()];
+
+    for _ in 0..settings.iterations {
+        let lambda = (0..predicted.len())
+            .map(|i| {
+                let mut gradient_i = DVec2::ZERO;
+                let mut gradient_sum = 0.0;
+                density[i] = mass[i] * sph::poly6(0.0, h);
+
+                for &j in &neighbours[i] {
+                    let r = predicted[i] - predicted[j];
+                    density[i] += mass[j] * sph::poly6(r.length(), h);
+
+                    let gradient = mass[j] / rho0 * sph::spiky_gradient(r, h);
+                    gradient_i += gradient;
+                    gradient_sum += gradient.length_squared();
+                }
+
+                let constraint = density[i] / rho0 - 1.0;
+                -constraint / (gradient_sum + gradient_i.length_squared() + settings.relaxation)
+            })
+            .collect::<Vec<_>>();
+
+        let corrections = (0..predicted.len())
+            .map(|i| {
+                neighbours[i]
+                    .iter()
+                    .map(|&j| {
+                        let r = predicted[i] - predicted[j];
+                        let s_corr = -settings.tensile_correction
+                            * (sph::poly6(r.length(), h) / w_dq).powi(4);
+
+                        mass[j] * (lambda[i] + lambda[j] + s_corr) * sph::spiky_gradient(r, h)
+                    })
+                    .sum::<DVec2>()
+                    / rho0
+            })
+            .collect::<Vec<_>>();
+
+        for (i, correction) in corrections.into_iter().enumerate() {
+            let radius = particles[i].1;
+
+            predicted[i] = (predicted[i] + correction).clamp(
+                DVec2::splat(radius),
+                DVec2::new(size.x as f64, size.y as f64) - radius,
+            );
+        }
+    }
+
+    let velocity = predicted
+        .iter()
+        .zip(&previous)
+        .map(|(pos, prev)| (*pos - *prev) / dt)
+        .collect::<Vec<_>>();
+
+    let vorticity = (0..predicted.len())
+        .map(|i| {
+            neighbours[i]
+                .iter()
+                .map(|&j| {
+                    let gradient = sph::spiky_gradient(predicted[i] - predicted[j], h);
+                    (velocity[j] - velocity[i]).perp_dot(-gradient)
+                })
+                .sum::<f64>()
+        })
+        .collect::<Vec<_>>();
+
+    let velocity = (0..predicted.len())
+        .map(|i| {
+            let mut vel = velocity[i];
+
+            // vorticity confinement, pushes towards higher vorticity to restore lost rotation
+            let eta = neighbours[i]
+                .iter()
+                .map(|&j| {
+                    mass[j] / density[j].max(f64::EPSILON)
+                        * vorticity[j].abs()
+                        * sph::spiky_gradient(predicted[i] - predicted[j], h)
+                })
+                .sum::<DVec2>();
+
+            if let Some(n) = eta.try_normalize() {
+                vel += settings.vorticity_confinement * DVec2::new(n.y, -n.x) * vorticity[i] * dt;
+            }
+
+            // XSPH viscosity
+            vel += settings.viscosity
+                * neighbours[i]
+                    .iter()
+                    .map(|&j| {
+                        mass[j] / density[j].max(f64::EPSILON)
+                            * (velocity[j] - velocity[i])
+                            * sph::poly6(predicted[i].distance(predicted[j]), h)
+                    })
+                    .sum::<DVec2>();
+
+            vel
+        })
+        .collect::<Vec<_>>();
+
+    query
+        .iter_mut(world)
+        .enumerate()
+        .for_each(|(i, (_, _, pos, vel, fluid))| {
+            pos.0 = predicted[i];
+            vel.0 = velocity[i];
+            fluid.density = density[i];
+            fluid.pressure = 0.0;
         });
+}
+
+#[system]
+pub fn measure_energy(
+    world: &mut SubWorld,
+    query: &mut Query<(&Mass, &Velocity)>,
+    #[resource] energy: &mut EnergyStats,
+) {
+    energy.kinetic = query
+        .iter(world)
+        .map(|(Mass(m), Velocity(v))| 0.5 * m * v.length_squared())
+        .sum();
+}
+
+#[system]
+#[read_component(Position)]
+#[read_component(Mass)]
+#[read_component(Pinned)]
+#[write_component(Velocity)]
+pub fn apply_springs(
+    world: &mut SubWorld,
+    springs: &mut Query<(Entity, &Spring)>,
+    cmd: &mut CommandBuffer,
+    #[resource] dt: &f32,
+) {
+    let dt = *dt as f64;
+    let springs = springs
+        .iter(world)
+        .map(|(entity, spring)| (*entity, *spring))
+        .collect::<Vec<_>>();
+
+    for (entity, spring) in springs {
+        // one of the particles was removed
+        let (Some((pos_a, vel_a, w_a)), Some((pos_b, vel_b, w_b))) =
+            (link_end(world, spring.a), link_end(world, spring.b))
+        else {
+            cmd.remove(entity);
+            continue;
+        };
+
+        let distance = pos_a.distance(pos_b);
+        if distance == 0.0 {
+            continue;
+        }
+
+        let normal = (pos_b - pos_a) / distance;
+        let stretch = distance - spring.rest_length;
+        let relative_speed = (vel_b - vel_a).dot(normal);
+
+        // pulls a towards b when stretched
+        let force = (spring.stiffness * stretch + spring.damping * relative_speed) * normal;
+
+        add_velocity(world, spring.a, force * w_a * dt);
+        add_velocity(world, spring.b, -force * w_b * dt);
     }
+}
 
-    time.0 = clock.elapsed().as_nanos();
+/// Projects the linked particles back to their constraint length
+/// and removes the relative velocity along the link
+#[system]
+#[read_component(Mass)]
+#[read_component(Pinned)]
+#[write_component(Position)]
+#[write_component(Velocity)]
+pub fn solve_distance_constraints(
+    world: &mut SubWorld,
+    constraints: &mut Query<(Entity, &DistanceConstraint)>,
+    cmd: &mut CommandBuffer,
+    #[resource] settings: &ConstraintSettings,
+) {
+    let constraints = constraints
+        .iter(world)
+        .map(|(entity, constraint)| (*entity, *constraint))
+        .collect::<Vec<_>>();
+
+    for iteration in 0..settings.iterations {
+        for (entity, DistanceConstraint { a, b, length }) in &constraints {
+            let (Some((pos_a, vel_a, w_a)), Some((pos_b, vel_b, w_b))) =
+                (link_end(world, *a), link_end(world, *b))
+            else {
+                if iteration == 0 {
+                    cmd.remove(*entity);
+                }
+
+                continue;
+            };
+
+            let distance = pos_a.distance(pos_b);
+            if distance == 0.0 || w_a + w_b == 0.0 {
+                continue;
+            }
+
+            let normal = (pos_b - pos_a) / distance;
+            let correction = (distance - length) / (w_a + w_b) * normal;
+            let impulse = (vel_b - vel_a).dot(normal) / (w_a + w_b) * normal;
+
+            for (entity, w, sign) in [(*a, w_a, 1.0), (*b, w_b, -1.0)] {
+                if let Ok(mut entry) = world.entry_mut(entity) {
+                    entry.get_component_mut::<Position>().unwrap().0 += sign * w * correction;
+                    entry.get_component_mut::<Velocity>().unwrap().0 += sign * w * impulse;
+                }
+            }
+        }
+    }
+}
+
+/// Pressure pushes the outline outwards and shape matching pulls
+/// the members towards the rotated rest shape
+#[system]
+#[read_component(Position)]
+#[read_component(Mass)]
+#[write_component(Velocity)]
+pub fn apply_soft_bodies(
+    world: &mut SubWorld,
+    bodies: &mut Query<(Entity, &SoftBody)>,
+    cmd: &mut CommandBuffer,
+    #[resource] dt: &f32,
+) {
+    let dt = *dt as f64;
+    let bodies = bodies
+        .iter(world)
+        .map(|(entity, body)| (*entity, body.clone()))
+        .collect::<Vec<_>>();
+
+    let state = |world: &SubWorld, entity| {
+        let entry = world.entry_ref(entity).ok()?;
+        let Position(pos) = entry.get_component::<Position>().ok()?;
+        let Mass(mass) = entry.get_component::<Mass>().ok()?;
+
+        Some((*pos, *mass))
+    };
+
+    for (entity, body) in bodies {
+        let Some(members) = body
+            .members
+            .iter()
+            .map(|e| state(world, *e))
+            .collect::<Option<Vec<_>>>()
+        else {
+            // a member was removed
+            cmd.remove(entity);
+            continue;
+        };
+
+        if body.pressure != 0.0 {
+            let outline = body
+                .outline
+                .iter()
+                .filter_map(|e| state(world, *e).map(|(pos, _)| pos))
+                .collect::<Vec<_>>();
+
+            let area = polygon_area(&outline);
+            let pressure = body.pressure * body.rest_area / area.abs().max(1.0);
+
+            for i in 0..outline.len() {
+                let j = (i + 1) % outline.len();
+                let edge = outline[j] - outline[i];
+                // outward normal scaled by the edge length
+                let force = pressure * DVec2::new(edge.y, -edge.x) * area.signum();
+
+                for k in [i, j] {
+                    let (_, mass) = state(world, body.outline[k]).unwrap();
+                    add_velocity(world, body.outline[k], 0.5 * force / mass * dt);
+                }
+            }
+        }
+
+        if body.shape_stiffness > 0.0 {
+            let total_mass = members.iter().map(|(_, m)| m).sum::<f64>();
+            let centre = members.iter().map(|(p, m)| *p * *m).sum::<DVec2>() / total_mass;
+
+            // best fitting rotation of the rest shape
+            let (dot, cross) = members.iter().zip(&body.rest_shape).fold(
+                (0.0, 0.0),
+                |(dot, cross), ((pos, m), rest)| {
+                    let p = *pos - centre;
+                    (dot + m * rest.dot(p), cross + m * rest.perp_dot(p))
+                },
+            );
+            let rotation = DVec2::from_angle(cross.atan2(dot));
+
+            for ((member, (pos, _)), rest) in
+                body.members.iter().zip(&members).zip(&body.rest_shape)
+            {
+                let goal = centre + rotation.rotate(*rest);
+                add_velocity(world, *member, body.shape_stiffness * (goal - *pos) / dt);
+            }
+        }
+    }
+}
+
+/// Gathers the velocity changes every member received this step (collisions, walls, forces)
+/// as impulses on the whole body, then moves the members rigidly with the body
+#[system]
+#[read_component(Mass)]
+#[write_component(Position)]
+#[write_component(Velocity)]
+pub fn solve_rigid_bodies(
+    world: &mut SubWorld,
+    bodies: &mut Query<(Entity, &mut RigidBody)>,
+    cmd: &mut CommandBuffer,
+    #[resource] dt: &f32,
+) {
+    let dt = *dt as f64;
+    let bodies = bodies
+        .iter_mut(world)
+        .map(|(entity, body)| (*entity, body.clone()))
+        .collect::<Vec<_>>();
+
+    for (entity, mut body) in bodies {
+        let Some(members) = body
+            .members
+            .iter()
+            .map(|(e, _)| {
+                let entry = world.entry_ref(*e).ok()?;
+                let Position(pos) = entry.get_component::<Position>().ok()?;
+                let Velocity(vel) = entry.get_component::<Velocity>().ok()?;
+                let Mass(mass) = entry.get_component::<Mass>().ok()?;
+
+                Some((*pos, *vel, *mass))
+            })
+            .collect::<Option<Vec<_>>>()
+        else {
+            // a member was removed, the rest are free particles now
+            for (member, _) in &body.members {
+                if world.entry_ref(*member).is_ok() {
+                    cmd.remove_component::<RigidMember>(*member);
+                }
+            }
+
+            cmd.remove(entity);
+            continue;
+        };
+
+        let rotation = DVec2::from_angle(body.angle);
+        let mut momentum = body.velocity * body.mass;
+        let mut angular_momentum = body.angular_velocity * body.inertia;
+        let mut centre = DVec2::ZERO;
+
+        for ((_, offset), (pos, vel, mass)) in body.members.iter().zip(&members) {
+            let r = rotation.rotate(*offset);
+            let expected = body.velocity + body.angular_velocity * r.perp();
+            let impulse = mass * (*vel - expected);
+
+            momentum += impulse;
+            angular_momentum += r.perp_dot(impulse);
+            centre += *pos * *mass;
+        }
+
+        body.velocity = momentum / body.mass;
+        body.angular_velocity = angular_momentum / body.inertia;
+        // members already moved this step, including wall and overlap corrections
+        body.position = centre / body.mass;
+        body.angle += body.angular_velocity * dt;
+
+        let rotation = DVec2::from_angle(body.angle);
+
+        for (member, offset) in &body.members {
+            let r = rotation.rotate(*offset);
+
+            if let Ok(mut entry) = world.entry_mut(*member) {
+                entry.get_component_mut::<Position>().unwrap().0 = body.position + r;
+                entry.get_component_mut::<Velocity>().unwrap().0 =
+                    body.velocity + body.angular_velocity * r.perp();
+            }
+        }
+
+        if let Ok(mut entry) = world.entry_mut(entity) {
+            *entry.get_component_mut::<RigidBody>().unwrap() = body;
+        }
+    }
+}
+
+/// Damped spring between each grabbed particle and the mouse, solved implicitly
+/// for the new velocity so stiff springs and long frames don't blow up
+#[system(for_each)]
+pub fn drag_grabbed(
+    Position(pos): &Position,
+    Velocity(vel): &mut Velocity,
+    grabbed: &Grabbed,
+    #[resource] settings: &GrabSettings,
+    #[resource] dt: &f32,
+) {
+    let dt = *dt as f64;
+    let (k, c) = (settings.stiffness, settings.damping);
+
+    *vel = (*vel + dt * (k * (grabbed.anchor - *pos) + c * grabbed.anchor_velocity))
+        / (1.0 + c * dt + k * dt * dt);
+}
+
+#[system(for_each)]
+pub fn enforce_pins(pos: &mut Position, vel: &mut Velocity, Pinned(anchor): &Pinned) {
+    pos.0 = *anchor;
+    vel.0 = DVec2::ZERO;
 }
 
 #[system(for_each)]
@@ -165,26 +1562,246 @@ pub fn update_velocity(vel: &mut Velocity, #[resource] dt: &f32) {
     vel.0.y += GRAVITY * *dt as f64;
 }
 
+/// F = -(k1 + k2 * |v|) * v, integrated implicitly so that large
+/// coefficients damp the velocity instead of reversing it
 #[system(for_each)]
+pub fn apply_drag(
+    vel: &mut Velocity,
+    Mass(mass): &Mass,
+    ShapeInfo { radius, .. }: &ShapeInfo,
+    drag: Option<&Drag>,
+    #[resource] settings: &DragSettings,
+    #[resource] dt: &f32,
+) {
+    if !settings.enabled {
+        return;
+    }
+
+    let (linear, quadratic) = match drag {
+        Some(Drag { linear, quadratic }) => (*linear, *quadratic),
+        None => (settings.linear, settings.quadratic),
+    };
+
+    let cross_section = if settings.use_cross_section {
+        2.0 * radius
+    } else {
+        1.0
+    };
+
+    let k = cross_section * (linear + quadratic * vel.0.length()) / mass;
+    vel.0 /= 1.0 + k * *dt as f64;
+}
+
+/// Relaxes velocities towards the external flow, exactly for any time step
+#[system(for_each)]
+pub fn apply_flow_drag(
+    Position(pos): &Position,
+    Velocity(vel): &mut Velocity,
+    #[resource] field: &VectorField,
+    #[resource] dt: &f32,
+) {
+    if !field.enabled {
+        return;
+    }
+
+    if let Some(flow) = field.sample(*pos) {
+        *vel += (flow - *vel) * (1.0 - (-field.coupling * *dt as f64).exp());
+    }
+}
+
+#[system(for_each)]
+#[allow(clippy::too_many_arguments)]
 pub fn check_wall_collision(
+    entity: &Entity,
     pos: &mut Position,
     vel: &mut Velocity,
     ShapeInfo { radius, .. }: &ShapeInfo,
+    Mass(mass): &Mass,
+    temperature: Option<&mut Temperature>,
+    specific_heat: Option<&HeatCapacity>,
+    group: Option<&CollisionGroup>,
     #[resource] size: &sfml::system::Vector2u,
+    #[resource] heat: &HeatSettings,
+    #[resource] events: &mut CollisionEvents,
+    #[resource] dt: &f32,
 ) {
+    if !group
+        .copied()
+        .unwrap_or_default()
+        .hits(CollisionGroup::WALLS)
+    {
+        return;
+    }
+
+    let mut touched = [false; 4];
+    let mut hit = |wall, point, normal, speed: f64| {
+        touched[wall as usize] = true;
+        events.walls.push(WallHit {
+            entity: *entity,
+            wall,
+            point,
+            normal,
+            impulse: 2.0 * mass * speed.abs(),
+        });
+    };
+
+    let right = (size.x - WINDOW_PADDING) as f64;
+    let bottom = (size.y - WINDOW_PADDING) as f64;
+
     if pos.0.x - *radius < 0.0 {
+        hit(Wall::Left, DVec2::new(0.0, pos.0.y), DVec2::X, vel.0.x);
         vel.0.x *= -1.0;
         pos.0.x = *radius;
-    } else if pos.0.x + radius >= (size.x - WINDOW_PADDING) as f64 {
+    } else if pos.0.x + radius >= right {
+        hit(
+            Wall::Right,
+            DVec2::new(right, pos.0.y),
+            DVec2::NEG_X,
+            vel.0.x,
+        );
         vel.0.x *= -1.0;
-        pos.0.x = (size.x - WINDOW_PADDING) as f64 - radius;
+        pos.0.x = right - radius;
     }
 
     if pos.0.y - *radius < 0.0 {
+        hit(Wall::Top, DVec2::new(pos.0.x, 0.0), DVec2::Y, vel.0.y);
         vel.0.y *= -1.0;
         pos.0.y = *radius;
-    } else if pos.0.y + radius >= (size.y - WINDOW_PADDING) as f64 {
+    } else if pos.0.y + radius >= bottom {
+        hit(
+            Wall::Bottom,
+            DVec2::new(pos.0.x, bottom),
+            DVec2::NEG_Y,
+            vel.0.y,
+        );
         vel.0.y *= -1.0;
-        pos.0.y = (size.y - WINDOW_PADDING) as f64 - radius;
+        pos.0.y = bottom - radius;
+    }
+
+    let (Some(temperature), Some(HeatCapacity(c))) = (temperature, specific_heat) else {
+        return;
+    };
+
+    if !heat.enabled {
+        return;
+    }
+
+    for (bath, _) in heat
+        .baths
+        .iter()
+        .zip(touched)
+        .filter(|(_, touched)| *touched)
+    {
+        if let Some(bath) = bath {
+            // the bath's heat capacity is infinite
+            temperature.0 += heat_flow(
+                temperature.0,
+                mass * c,
+                *bath,
+                f64::INFINITY,
+                heat.bath_conductance * radius,
+                *dt as f64,
+            ) / (mass * c);
+        }
+    }
+}
+
+#[system(for_each)]
+pub fn run_emitters(emitter: &mut Emitter, cmd: &mut CommandBuffer, #[resource] dt: &f32) {
+    let mut rng = rand::thread_rng();
+
+    emitter.accumulator += emitter.rate * *dt as f64;
+
+    while emitter.accumulator >= 1.0 {
+        if emitter.max_count.is_some_and(|max| emitter.emitted >= max) {
+            emitter.accumulator = 0.0;
+            break;
+        }
+
+        // time since this particle would have left the emitter within the step
+        let age = (emitter.accumulator - 1.0) / emitter.rate;
+
+        emitter.accumulator -= 1.0;
+        emitter.emitted += 1;
+
+        let template = ParticleTemplate {
+            radius: emitter.radius.sample(&mut rng),
+            mass: emitter.mass.sample(&mut rng),
+            charge: 0.0,
+            color: emitter.color,
+            temperature: emitter.temperature,
+            specific_heat: 1.0,
+        };
+
+        let angle = emitter.direction + emitter.spread * rng.gen_range(-0.5..=0.5);
+        let vel = DVec2::from_angle(angle) * emitter.speed.sample(&mut rng);
+        // spread the particles of one step along their path instead of stacking them
+        let entity = cmd.push(template.components(emitter.position + vel * age, vel));
+
+        if let Some(lifetime) = emitter.lifetime {
+            cmd.add_component(entity, Lifetime(lifetime));
+        }
+    }
+}
+
+#[system(for_each)]
+pub fn expire_particles(
+    entity: &Entity,
+    lifetime: &mut Lifetime,
+    cmd: &mut CommandBuffer,
+    #[resource] dt: &f32,
+) {
+    lifetime.0 -= *dt as f64;
+
+    if lifetime.0 <= 0.0 {
+        cmd.remove(*entity);
+    }
+}
+
+/// Looks the items up by particle id, ids are never reused
+/// so they get too sparse over time to index a vec with
+pub fn index_by_id<T>(items: impl Iterator<Item = (usize, T)>) -> HashMap<usize, T> {
+    items.collect()
+}
+
+/// Possible interaction partners of a particle, taken from the
+/// neighbour list when it's enabled, otherwise queried from the quadtree
+fn neighbours_of<'a>(
+    qt: &QuadTree<usize>,
+    list: &'a NeighbourList,
+    id: usize,
+    pos: DVec2,
+    radius: f64,
+) -> Cow<'a, [usize]> {
+    if list.enabled {
+        Cow::Borrowed(list.neighbours(id))
+    } else {
+        Cow::Owned(qt.query(pos, radius))
+    }
+}
+
+/// Position, velocity and inverse mass of a linked particle,
+/// pinned particles have an inverse mass of zero
+fn link_end(world: &SubWorld, entity: Entity) -> Option<(DVec2, DVec2, f64)> {
+    let entry = world.entry_ref(entity).ok()?;
+
+    let Position(pos) = entry.get_component::<Position>().ok()?;
+    let Velocity(vel) = entry.get_component::<Velocity>().ok()?;
+    let Mass(mass) = entry.get_component::<Mass>().ok()?;
+
+    let inverse_mass = if entry.get_component::<Pinned>().is_ok() {
+        0.0
+    } else {
+        1.0 / mass
+    };
+
+    Some((*pos, *vel, inverse_mass))
+}
+
+fn add_velocity(world: &mut SubWorld, entity: Entity, dv: DVec2) {
+    if let Ok(mut entry) = world.entry_mut(entity)
+        && let Ok(vel) = entry.get_component_mut::<Velocity>()
+    {
+        vel.0 += dv;
     }
 }